@@ -0,0 +1,56 @@
+use postgres::Error;
+use chrono::NaiveDate;
+use serde::Serialize;
+
+use crate::Db;
+
+// Percentage of the usual prescription suggested after a stall.
+pub const DELOAD_PERCENT: f32 = 90.0;
+
+// Sessions with a missed prescription within the checked sessions that count as a stall.
+const FAILED_SESSIONS_STALL: usize = 2;
+
+#[derive(Serialize)]
+pub struct StallReport {
+    // Sessions since the best e1RM was set. The session that set it doesn't count.
+    pub sessions_since_pr: usize,
+    pub best_e1rm: f32,
+    pub best_e1rm_date: NaiveDate,
+    // Sessions among the last checked ones with fewer reps or less weight than prescribed.
+    pub failed_sessions: usize,
+    pub sessions: usize,
+}
+
+impl StallReport {
+    pub fn is_stalled(&self) -> bool {
+        self.sessions_since_pr >= self.sessions || self.failed_sessions >= FAILED_SESSIONS_STALL
+    }
+}
+
+impl Db {
+    // Looks for a plateau over the last `sessions` sessions of the exercise. None without any lifts.
+    pub fn select_stall_report(&mut self, exercise_id: i32, sessions: usize) -> Result<Option<StallReport>, Error> {
+        let query = self.client.query("SELECT date, MAX(e1rm) FROM lift_loads WHERE exercise_id=$1 GROUP BY session_id, date ORDER BY date, session_id;", &[&exercise_id])?;
+        let mut best: Option<(f32, NaiveDate)> = None;
+        let mut sessions_since_pr = 0;
+        for row in query {
+            let e1rm: f32 = row.get(1);
+            match best {
+                Some((best_e1rm, _)) if e1rm <= best_e1rm => sessions_since_pr += 1,
+                _ => {best = Some((e1rm, row.get(0))); sessions_since_pr = 0;},
+            }
+        }
+        let (best_e1rm, best_e1rm_date) = match best {
+            Some(b) => b,
+            None => return Ok(None),
+        };
+
+        // Weights can be entered in lb, so allow for conversion noise when comparing to the kg prescription.
+        let failed: i64 = self.client.query_one("
+            SELECT COUNT(*) FROM (SELECT l.session_id FROM lifts l JOIN sessions s ON s.id=l.session_id WHERE l.exercise_id=$1 GROUP BY l.session_id, s.date ORDER BY s.date DESC LIMIT $2) recent
+            WHERE EXISTS (SELECT 1 FROM lifts l WHERE l.session_id=recent.session_id AND l.exercise_id=$1 AND (l.reps < l.prescribed_reps OR l.weight < l.prescribed_weight * 0.99));",
+            &[&exercise_id, &(sessions as i64)])?.get(0);
+
+        Ok(Some(StallReport {sessions_since_pr, best_e1rm, best_e1rm_date, failed_sessions: failed as usize, sessions}))
+    }
+}
@@ -0,0 +1,398 @@
+use std::error::Error;
+use std::io::Read;
+use chrono::{NaiveDate, NaiveDateTime};
+
+use crate::{Db, Unit};
+use crate::import::{ImportReport, ImportRow};
+
+// A row and its line number in the file.
+type LineRow = (u64, ImportRow);
+
+// Exports of other lifting apps. All of them write one row per set.
+#[derive(Clone, Copy, PartialEq)]
+pub enum App {
+    // Date;Workout Name;Exercise Name;Set Order;Weight;Weight Unit;Reps;...;Notes, older exports use commas and no unit.
+    Strong,
+    // title,start_time,end_time,...,exercise_title,...,set_type,weight_kg or weight_lbs,reps,...
+    Hevy,
+    // Date,Exercise,Category,Weight (kgs) or Weight (lbs),Reps,...,Comment
+    FitNotes,
+}
+
+// Names of the apps' built-in exercises and the exercise they are in liftsql.
+// Names that aren't listed are matched against exercise names and aliases as they are.
+const STRONG_NAMES: [(&str, &str); 10] = [
+    ("Squat (Barbell)", "Squat"), ("Bench Press (Barbell)", "Bench"), ("Deadlift (Barbell)", "Deadlift"),
+    ("Overhead Press (Barbell)", "Press"), ("Chin Up", "Chinups"), ("Clean (Barbell)", "Clean"),
+    ("Lat Pulldown (Cable)", "Lat pulldowns"), ("Front Squat (Barbell)", "Front squat"), ("Bent Over Row (Barbell)", "Rows"),
+    ("Snatch (Barbell)", "Snatch"),
+];
+const HEVY_NAMES: [(&str, &str); 11] = [
+    ("Squat (Barbell)", "Squat"), ("Bench Press (Barbell)", "Bench"), ("Deadlift (Barbell)", "Deadlift"),
+    ("Overhead Press (Barbell)", "Press"), ("Chin Up", "Chinups"), ("Chin Up (Weighted)", "Chinups"), ("Clean", "Clean"),
+    ("Lat Pulldown (Cable)", "Lat pulldowns"), ("Front Squat", "Front squat"), ("Bent Over Row (Barbell)", "Rows"),
+    ("Snatch", "Snatch"),
+];
+const FITNOTES_NAMES: [(&str, &str); 10] = [
+    ("Barbell Squat", "Squat"), ("Flat Barbell Bench Press", "Bench"), ("Deadlift", "Deadlift"),
+    ("Overhead Press", "Press"), ("Chin Up", "Chinups"), ("Power Clean", "Clean"),
+    ("Lat Pulldown", "Lat pulldowns"), ("Barbell Front Squat", "Front squat"), ("Barbell Row", "Rows"),
+    ("Snatch", "Snatch"),
+];
+
+impl App {
+    pub const ALL: [App; 3] = [App::Strong, App::Hevy, App::FitNotes];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            App::Strong => "strong",
+            App::Hevy => "hevy",
+            App::FitNotes => "fitnotes",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<App> {
+        App::ALL.into_iter().find(|a| a.as_str() == s.to_lowercase())
+    }
+
+    // Guesses the app from the header row.
+    pub fn detect(headers: &csv::StringRecord) -> Option<App> {
+        let has = |name: &str| headers.iter().any(|h| h.trim().eq_ignore_ascii_case(name));
+        if has("exercise_title") && has("start_time") {
+            Some(App::Hevy)
+        } else if has("Exercise Name") && has("Set Order") {
+            Some(App::Strong)
+        } else if has("Exercise") && has("Category") {
+            Some(App::FitNotes)
+        } else {
+            None
+        }
+    }
+
+    fn exercise_names(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            App::Strong => &STRONG_NAMES,
+            App::Hevy => &HEVY_NAMES,
+            App::FitNotes => &FITNOTES_NAMES,
+        }
+    }
+}
+
+pub struct AppImportOptions {
+    // None to detect the app from the header.
+    pub app: Option<App>,
+    // Unit of exports that don't say which one they use.
+    pub unit: Unit,
+    pub create_exercises: bool,
+    pub dry_run: bool,
+}
+
+struct Columns {
+    date: usize,
+    exercise: usize,
+    weight: Option<usize>,
+    unit: Option<usize>,
+    reps: usize,
+    notes: Option<usize>,
+    rpe: Option<usize>,
+    set_type: Option<usize>,
+}
+
+fn column(headers: &csv::StringRecord, names: &[&str]) -> Option<usize> {
+    headers.iter().position(|header| names.iter().any(|name| header.trim().eq_ignore_ascii_case(name)))
+}
+
+fn required_column(headers: &csv::StringRecord, names: &[&str]) -> Result<usize, Box<dyn Error>> {
+    column(headers, names).ok_or(format!("column '{}' not found in the header", names[0]).into())
+}
+
+fn parse_date(app: App, s: &str) -> Option<NaiveDate> {
+    let formats: &[&str] = match app {
+        App::Strong => &["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"],
+        App::Hevy => &["%d %b %Y, %H:%M", "%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S"],
+        App::FitNotes => &[],
+    };
+    if let Some(datetime) = formats.iter().find_map(|format| NaiveDateTime::parse_from_str(s, format).ok()) {
+        return Some(datetime.date());
+    }
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()
+}
+
+// The lifts of an export with consecutive equal sets merged, and the app it came from.
+fn read_rows(content: &str, options: &AppImportOptions, report: &mut ImportReport) -> Result<(App, Vec<LineRow>), Box<dyn Error>> {
+    // Newer Strong exports are separated by semicolons.
+    let first_line = content.lines().next().unwrap_or("");
+    let delimiter = if first_line.matches(';').count() > first_line.matches(',').count() { b';' } else { b',' };
+    let mut csv = csv::ReaderBuilder::new().delimiter(delimiter).flexible(true).from_reader(content.as_bytes());
+    let headers = csv.headers()?.clone();
+    let app = match options.app.or_else(|| App::detect(&headers)) {
+        Some(app) => app,
+        None => return Err("couldn't tell which app the file was exported from".into()),
+    };
+
+    let columns = match app {
+        App::Strong => Columns {
+            date: required_column(&headers, &["Date"])?,
+            exercise: required_column(&headers, &["Exercise Name"])?,
+            weight: column(&headers, &["Weight"]),
+            unit: column(&headers, &["Weight Unit"]),
+            reps: required_column(&headers, &["Reps"])?,
+            notes: column(&headers, &["Notes"]),
+            rpe: column(&headers, &["RPE"]),
+            set_type: column(&headers, &["Set Order"]),
+        },
+        App::Hevy => Columns {
+            date: required_column(&headers, &["start_time"])?,
+            exercise: required_column(&headers, &["exercise_title"])?,
+            weight: column(&headers, &["weight_kg", "weight_lbs"]),
+            unit: None,
+            reps: required_column(&headers, &["reps"])?,
+            notes: column(&headers, &["exercise_notes"]),
+            rpe: column(&headers, &["rpe"]),
+            set_type: column(&headers, &["set_type"]),
+        },
+        App::FitNotes => Columns {
+            date: required_column(&headers, &["Date"])?,
+            exercise: required_column(&headers, &["Exercise"])?,
+            weight: column(&headers, &["Weight (kgs)", "Weight (lbs)", "Weight"]),
+            unit: column(&headers, &["Weight Unit"]),
+            reps: required_column(&headers, &["Reps"])?,
+            notes: column(&headers, &["Comment"]),
+            rpe: None,
+            set_type: None,
+        },
+    };
+    // The unit is in the weight column's name, in its own column or not in the file at all.
+    let header_unit = columns.weight.and_then(|index| {
+        let header = headers[index].to_lowercase();
+        if header.contains("lbs") {
+            Some(Unit::Lb)
+        } else if header.contains("kg") {
+            Some(Unit::Kg)
+        } else {
+            None
+        }
+    });
+
+    let mut rows: Vec<LineRow> = Vec::new();
+    for record in csv.records() {
+        let record = record?;
+        let line = record.position().map(|p| p.line()).unwrap_or(0);
+        let field = |index: Option<usize>| index.and_then(|i| record.get(i)).map(|f| f.trim()).unwrap_or("");
+        report.rows += 1;
+
+        // Strong writes rest timers as rows, both mark warmups. Neither are lifts, and neither is cardio without reps.
+        let set_type = field(columns.set_type).to_lowercase();
+        if set_type == "warmup" || set_type == "w" || set_type == "rest timer" {
+            report.rows_skipped += 1;
+            continue;
+        }
+        let reps = match field(Some(columns.reps)) {
+            "" => 0.0,
+            reps => match reps.parse::<f32>() {
+                Ok(reps) => reps,
+                Err(_) => {
+                    report.errors.push((line, format!("invalid reps '{}'", reps)));
+                    continue;
+                },
+            },
+        };
+        if reps <= 0.0 {
+            report.rows_skipped += 1;
+            continue;
+        }
+
+        let date_str = field(Some(columns.date));
+        let date = match parse_date(app, date_str) {
+            Some(date) => date,
+            None => {
+                report.errors.push((line, format!("invalid date '{}'", date_str)));
+                continue;
+            },
+        };
+        let weight = match field(columns.weight) {
+            "" => 0.0,
+            weight => match weight.parse::<f32>() {
+                Ok(weight) => weight,
+                Err(_) => {
+                    report.errors.push((line, format!("invalid weight '{}'", weight)));
+                    continue;
+                },
+            },
+        };
+        let unit = Unit::parse(field(columns.unit)).or(header_unit).unwrap_or(options.unit);
+
+        let app_name = field(Some(columns.exercise));
+        let exercise = match app.exercise_names().iter().find(|n| n.0.eq_ignore_ascii_case(app_name)) {
+            Some((_, name)) => {
+                if !report.mapped_exercises.iter().any(|m| m.0 == app_name) {
+                    report.mapped_exercises.push((app_name.to_string(), name.to_string()));
+                }
+                name.to_string()
+            },
+            None => app_name.to_string(),
+        };
+        let notes = match field(columns.notes) {
+            "" => None,
+            notes => Some(notes.to_string()),
+        };
+        let rpe = field(columns.rpe).parse::<f32>().ok().filter(|rpe| *rpe > 0.0);
+        let session_key = Some(format!("{}:{}", app.as_str(), date_str));
+
+        if let Some((_, last)) = rows.last_mut() {
+            if last.session_key == session_key && last.exercise == exercise && last.weight == weight && last.unit == unit && last.reps == reps && last.notes == notes && last.rpe == rpe {
+                last.sets += 1.0;
+                continue;
+            }
+        }
+        rows.push((line, ImportRow {date, session_key, exercise, weight, unit, reps, sets: 1.0, notes, rpe}));
+    }
+    Ok((app, rows))
+}
+
+impl Db {
+    // Imports an export of Strong, Hevy or FitNotes. Every workout becomes a session keyed by the app and the
+    // workout's start, so importing a newer export of the same app only adds the workouts that are new.
+    // FitNotes exports only have the date of a workout, so two of its workouts on one day become one session.
+    // Consecutive equal sets are logged as one lift.
+    pub fn import_app_csv<R: Read>(&mut self, mut reader: R, options: &AppImportOptions) -> Result<(App, ImportReport), Box<dyn Error>> {
+        let mut report = ImportReport::default();
+        let mut content = String::new();
+        reader.read_to_string(&mut content)?;
+        let (app, rows) = read_rows(&content, options, &mut report)?;
+        self.import_rows(rows, options.create_exercises, options.dry_run, &mut report)?;
+        Ok((app, report))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STRONG: &str = "\
+Date;Workout Name;Duration;Exercise Name;Set Order;Weight;Weight Unit;Reps;RPE;Distance;Distance Unit;Seconds;Notes;Workout Notes;Workout Duration
+2024-03-06 18:30:00;Evening Workout;1h 2m;Squat (Barbell);W;60;kg;5;;0;;0;;;1h 2m
+2024-03-06 18:30:00;Evening Workout;1h 2m;Squat (Barbell);1;100;kg;5;8;0;;0;;;1h 2m
+2024-03-06 18:30:00;Evening Workout;1h 2m;Squat (Barbell);2;100;kg;5;8;0;;0;;;1h 2m
+2024-03-06 18:30:00;Evening Workout;1h 2m;Squat (Barbell);Rest Timer;0;kg;0;;0;;180;;;1h 2m
+2024-03-06 18:30:00;Evening Workout;1h 2m;Squat (Barbell);3;100;kg;4;9;0;;0;grindy;;1h 2m
+2024-03-06 18:30:00;Evening Workout;1h 2m;Hip Thrust (Barbell);1;225;lbs;8;;0;;0;;;1h 2m
+";
+
+    const STRONG_OLD: &str = "\
+Date,Workout Name,Exercise Name,Set Order,Weight,Reps,Distance,Seconds,Notes,Workout Notes,RPE
+2023-01-02 07:00:00,Morning,Bench Press (Barbell),1,80,5,0,0,,,
+2023-01-02 07:00:00,Morning,Bench Press (Barbell),2,80,five,0,0,,,
+";
+
+    const HEVY: &str = "\
+\"title\",\"start_time\",\"end_time\",\"description\",\"exercise_title\",\"superset_id\",\"exercise_notes\",\"set_index\",\"set_type\",\"weight_kg\",\"reps\",\"distance_km\",\"duration_seconds\",\"rpe\"
+\"Push\",\"6 Mar 2024, 18:30\",\"6 Mar 2024, 19:30\",\"\",\"Bench Press (Barbell)\",,\"\",0,\"warmup\",40,10,,,
+\"Push\",\"6 Mar 2024, 18:30\",\"6 Mar 2024, 19:30\",\"\",\"Bench Press (Barbell)\",,\"\",1,\"normal\",80,5,,,8
+\"Push\",\"6 Mar 2024, 18:30\",\"6 Mar 2024, 19:30\",\"\",\"Bench Press (Barbell)\",,\"\",2,\"normal\",80,5,,,8
+\"Push\",\"6 Mar 2024, 18:30\",\"6 Mar 2024, 19:30\",\"\",\"Chin Up\",,\"\",0,\"normal\",,8,,,
+\"Push\",\"6 Mar 2024, 18:30\",\"6 Mar 2024, 19:30\",\"\",\"Treadmill\",,\"\",0,\"normal\",,,2.5,900,
+\"Pull\",\"8 Mar 2024, 18:00\",\"8 Mar 2024, 19:00\",\"\",\"Chin Up\",,\"\",0,\"normal\",,8,,,
+";
+
+    const FITNOTES: &str = "\
+Date,Exercise,Category,Weight (lbs),Reps,Distance,Distance Unit,Time,Comment
+2024-03-06,Barbell Squat,Legs,225.0,5,,,,
+2024-03-06,Barbell Squat,Legs,225.0,5,,,,
+2024-03-06,Barbell Squat,Legs,225.0,5,,,,Last one slow
+";
+
+    fn options(app: Option<App>) -> AppImportOptions {
+        AppImportOptions {app, unit: Unit::Kg, create_exercises: false, dry_run: true}
+    }
+
+    fn read(content: &str) -> (App, Vec<ImportRow>, ImportReport) {
+        let mut report = ImportReport::default();
+        let (app, rows) = read_rows(content, &options(None), &mut report).unwrap();
+        (app, rows.into_iter().map(|row| row.1).collect(), report)
+    }
+
+    fn headers(content: &str) -> csv::StringRecord {
+        let delimiter = if content.contains(';') { b';' } else { b',' };
+        csv::ReaderBuilder::new().delimiter(delimiter).from_reader(content.as_bytes()).headers().unwrap().clone()
+    }
+
+    fn lift(row: &ImportRow) -> (&str, f32, Unit, f32, f32) {
+        (row.exercise.as_str(), row.weight, row.unit, row.reps, row.sets)
+    }
+
+    fn date(year: i32, month: u32, day: u32) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(year, month, day)
+    }
+
+    #[test]
+    fn detects_the_app() {
+        assert!(App::detect(&headers(STRONG)) == Some(App::Strong));
+        assert!(App::detect(&headers(STRONG_OLD)) == Some(App::Strong));
+        assert!(App::detect(&headers(HEVY)) == Some(App::Hevy));
+        assert!(App::detect(&headers(FITNOTES)) == Some(App::FitNotes));
+        assert!(App::detect(&headers("date,exercise,weight,reps,sets,notes")).is_none());
+    }
+
+    #[test]
+    fn parses_the_dates_of_each_app() {
+        assert_eq!(parse_date(App::Strong, "2024-03-06 18:30:00"), date(2024, 3, 6));
+        assert_eq!(parse_date(App::Strong, "2024-03-06 18:30"), date(2024, 3, 6));
+        assert_eq!(parse_date(App::Hevy, "6 Mar 2024, 18:30"), date(2024, 3, 6));
+        assert_eq!(parse_date(App::Hevy, "2024-03-06T18:30:00"), date(2024, 3, 6));
+        assert_eq!(parse_date(App::FitNotes, "2024-03-06"), date(2024, 3, 6));
+        assert_eq!(parse_date(App::Hevy, "06.03.2024"), None);
+        assert_eq!(parse_date(App::FitNotes, "6 Mar 2024, 18:30"), None);
+    }
+
+    #[test]
+    fn reads_strong_exports() {
+        let (app, rows, report) = read(STRONG);
+        assert!(app == App::Strong);
+        assert_eq!(rows.iter().map(lift).collect::<Vec<_>>(), vec![
+            ("Squat", 100.0, Unit::Kg, 5.0, 2.0),
+            ("Squat", 100.0, Unit::Kg, 4.0, 1.0),
+            ("Hip Thrust (Barbell)", 225.0, Unit::Lb, 8.0, 1.0),
+        ]);
+        assert_eq!(rows[0].rpe, Some(8.0));
+        assert_eq!(rows[1].notes.as_deref(), Some("grindy"));
+        assert_eq!(rows[0].session_key.as_deref(), Some("strong:2024-03-06 18:30:00"));
+        assert_eq!((report.rows, report.rows_skipped), (6, 2));
+        assert_eq!(report.mapped_exercises, vec![(String::from("Squat (Barbell)"), String::from("Squat"))]);
+    }
+
+    #[test]
+    fn reads_old_strong_exports_in_the_given_unit() {
+        let (_, rows, report) = read(STRONG_OLD);
+        assert_eq!(rows.iter().map(lift).collect::<Vec<_>>(), vec![("Bench", 80.0, Unit::Kg, 5.0, 1.0)]);
+        assert_eq!(report.errors, vec![(3, String::from("invalid reps 'five'"))]);
+    }
+
+    #[test]
+    fn reads_hevy_exports() {
+        let (app, rows, report) = read(HEVY);
+        assert!(app == App::Hevy);
+        assert_eq!(rows.iter().map(lift).collect::<Vec<_>>(), vec![
+            ("Bench", 80.0, Unit::Kg, 5.0, 2.0),
+            ("Chinups", 0.0, Unit::Kg, 8.0, 1.0),
+            ("Chinups", 0.0, Unit::Kg, 8.0, 1.0),
+        ]);
+        assert_eq!(rows.iter().map(|row| row.date).collect::<Vec<_>>(), vec![date(2024, 3, 6).unwrap(), date(2024, 3, 6).unwrap(), date(2024, 3, 8).unwrap()]);
+        assert_eq!((report.rows, report.rows_skipped), (6, 2));
+    }
+
+    #[test]
+    fn reads_fitnotes_exports() {
+        let (app, rows, _) = read(FITNOTES);
+        assert!(app == App::FitNotes);
+        assert_eq!(rows.iter().map(lift).collect::<Vec<_>>(), vec![("Squat", 225.0, Unit::Lb, 5.0, 2.0), ("Squat", 225.0, Unit::Lb, 5.0, 1.0)]);
+        assert_eq!(rows[1].notes.as_deref(), Some("Last one slow"));
+    }
+
+    #[test]
+    fn rejects_unknown_exports() {
+        let mut report = ImportReport::default();
+        assert!(read_rows("date,exercise,weight,reps\n2024-03-06,Squat,100,5\n", &options(None), &mut report).is_err());
+        assert!(read_rows("date,exercise,weight,reps\n", &options(Some(App::Hevy)), &mut report).is_err());
+    }
+}
@@ -0,0 +1,223 @@
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::io::{Read, Write};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::Db;
+
+// Bumped whenever the format changes. Older backups are still restored, newer ones are refused.
+pub const BACKUP_VERSION: u32 = 1;
+
+// Everything in the database plus the settings of the config file. IDs are only used to link
+// the records of one backup and are remapped on restore. Weights are in kg.
+#[derive(Serialize, Deserialize)]
+pub struct Backup {
+    pub version: u32,
+    pub created: NaiveDate,
+    pub settings: BTreeMap<String, String>,
+    pub exercises: Vec<BackupExercise>,
+    pub sessions: Vec<BackupSession>,
+    pub personal_records: Vec<BackupPersonalRecord>,
+    pub bodyweights: Vec<(NaiveDate, f32)>,
+    pub blocks: Vec<(NaiveDate, String)>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BackupExercise {
+    pub id: i32,
+    pub name: String,
+    pub archived: bool,
+    pub default_weight: Option<f32>,
+    pub default_reps: Option<f32>,
+    pub default_sets: Option<f32>,
+    pub bodyweight: bool,
+    pub pr_reps: Option<f32>,
+    pub category: Option<String>,
+    pub equipment: Option<String>,
+    pub unilateral: bool,
+    // Missing in backups written before exercises had a rest timer.
+    #[serde(default)]
+    pub rest_seconds: Option<i32>,
+    pub aliases: Vec<String>,
+    // Muscle and whether it's a primary one.
+    pub muscles: Vec<(String, bool)>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BackupSession {
+    pub id: i32,
+    pub date: NaiveDate,
+    pub import_key: Option<String>,
+    pub lifts: Vec<BackupLift>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BackupLift {
+    pub id: i32,
+    pub exercise_id: i32,
+    pub weight: f32,
+    pub unit: String,
+    pub reps: f32,
+    pub sets: f32,
+    pub prescribed_weight: Option<f32>,
+    pub prescribed_reps: Option<f32>,
+    pub notes: Option<String>,
+    // Missing in backups written before lifts had an RPE.
+    #[serde(default)]
+    pub rpe: Option<f32>,
+    #[serde(default)]
+    pub rest_seconds: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BackupPersonalRecord {
+    pub lift_id: i32,
+    pub kind: String,
+    pub value: f32,
+    pub previous: f32,
+}
+
+impl Backup {
+    pub fn write<W: Write>(&self, writer: W) -> Result<(), Box<dyn Error>> {
+        serde_json::to_writer_pretty(writer, self)?;
+        Ok(())
+    }
+
+    pub fn read<R: Read>(reader: R) -> Result<Backup, Box<dyn Error>> {
+        let backup: Backup = serde_json::from_reader(reader)?;
+        if backup.version > BACKUP_VERSION {
+            return Err(format!("backup version {} is newer than this liftsql supports ({})", backup.version, BACKUP_VERSION).into());
+        }
+        Ok(backup)
+    }
+}
+
+pub struct RestoreReport {
+    pub exercises_created: usize,
+    pub exercises_updated: usize,
+    pub sessions: usize,
+    pub lifts: usize,
+    // Names and aliases of the backup that already name another exercise of the database, and are left as they are.
+    pub conflicts: Vec<String>,
+}
+
+impl Db {
+    pub fn select_backup(&mut self, settings: BTreeMap<String, String>) -> Result<Backup, postgres::Error> {
+        let created = self.select_current_date()?;
+        let mut exercises: Vec<BackupExercise> = Vec::new();
+        for row in self.client.query("SELECT id, name, archived, default_weight, default_reps, default_sets, bodyweight, pr_reps, category, equipment, unilateral, rest_seconds FROM exercises ORDER BY id;", &[])? {
+            exercises.push(BackupExercise {
+                id: row.get(0), name: row.get(1), archived: row.get(2), default_weight: row.get(3), default_reps: row.get(4), default_sets: row.get(5),
+                bodyweight: row.get(6), pr_reps: row.get(7), category: row.get(8), equipment: row.get(9), unilateral: row.get(10), rest_seconds: row.get(11),
+                aliases: Vec::new(), muscles: Vec::new(),
+            });
+        }
+        for row in self.client.query("SELECT exercise_id, alias FROM exercise_aliases ORDER BY id;", &[])? {
+            if let Some(exercise) = exercises.iter_mut().find(|e| e.id == row.get::<_, i32>(0)) {
+                exercise.aliases.push(row.get(1));
+            }
+        }
+        for row in self.client.query("SELECT exercise_id, muscle, is_primary FROM exercise_muscles ORDER BY id;", &[])? {
+            if let Some(exercise) = exercises.iter_mut().find(|e| e.id == row.get::<_, i32>(0)) {
+                exercise.muscles.push((row.get(1), row.get(2)));
+            }
+        }
+
+        let mut sessions: Vec<BackupSession> = Vec::new();
+        let mut session_index: HashMap<i32, usize> = HashMap::new();
+        for row in self.client.query("SELECT id, date, import_key FROM sessions ORDER BY date, id;", &[])? {
+            session_index.insert(row.get(0), sessions.len());
+            sessions.push(BackupSession {id: row.get(0), date: row.get(1), import_key: row.get(2), lifts: Vec::new()});
+        }
+        for row in self.client.query("SELECT id, session_id, exercise_id, weight, unit, reps, sets, prescribed_weight, prescribed_reps, notes, rpe, rest_seconds FROM lifts ORDER BY id;", &[])? {
+            if let Some(&index) = session_index.get(&row.get::<_, i32>(1)) {
+                sessions[index].lifts.push(BackupLift {
+                    id: row.get(0), exercise_id: row.get(2), weight: row.get(3), unit: row.get(4), reps: row.get(5), sets: row.get(6),
+                    prescribed_weight: row.get(7), prescribed_reps: row.get(8), notes: row.get(9), rpe: row.get(10), rest_seconds: row.get(11),
+                });
+            }
+        }
+
+        let mut personal_records: Vec<BackupPersonalRecord> = Vec::new();
+        for row in self.client.query("SELECT lift_id, kind, value, previous FROM personal_records ORDER BY id;", &[])? {
+            personal_records.push(BackupPersonalRecord {lift_id: row.get(0), kind: row.get(1), value: row.get(2), previous: row.get(3)});
+        }
+        let bodyweights = self.client.query("SELECT date, weight FROM bodyweights ORDER BY date;", &[])?.iter().map(|row| (row.get(0), row.get(1))).collect();
+        let blocks = self.select_blocks()?;
+
+        Ok(Backup {version: BACKUP_VERSION, created, settings, exercises, sessions, personal_records, bodyweights, blocks})
+    }
+
+    // The exercise other than $2 that $1 is the name or an alias of.
+    const NAME_OF_OTHER_EXERCISE_QUERY: &'static str = "SELECT name FROM exercises WHERE lower(name)=lower($1) AND id<>$2 UNION ALL SELECT e.name FROM exercise_aliases a JOIN exercises e ON e.id=a.exercise_id WHERE lower(a.alias)=lower($1) AND a.exercise_id<>$2 LIMIT 1;";
+
+    // Restores a backup into a database without sessions. Exercises that already exist under the same name,
+    // like the ones a new database starts with, keep their IDs and take the attributes of the backup.
+    pub fn restore_backup(&mut self, backup: &Backup) -> Result<RestoreReport, Box<dyn Error>> {
+        let mut transaction = self.client.transaction()?;
+        let session_count: i64 = transaction.query_one("SELECT COUNT(*) FROM sessions;", &[])?.get(0);
+        if session_count > 0 {
+            return Err("the database already has sessions, backups are only restored into an empty database".into());
+        }
+
+        let mut report = RestoreReport {exercises_created: 0, exercises_updated: 0, sessions: 0, lifts: 0, conflicts: Vec::new()};
+        let mut exercise_ids: HashMap<i32, i32> = HashMap::new();
+        for exercise in &backup.exercises {
+            let id: i32 = match transaction.query_opt("SELECT id FROM exercises WHERE name=$1;", &[&exercise.name])? {
+                Some(row) => {
+                    report.exercises_updated += 1;
+                    row.get(0)
+                },
+                None => {
+                    report.exercises_created += 1;
+                    transaction.query_one("INSERT INTO exercises (name) VALUES ($1) returning id;", &[&exercise.name])?.get(0)
+                },
+            };
+            transaction.execute("UPDATE exercises SET archived=$2, default_weight=$3, default_reps=$4, default_sets=$5, bodyweight=$6, pr_reps=$7, category=$8, equipment=$9, unilateral=$10, rest_seconds=$11 WHERE id=$1;",
+                &[&id, &exercise.archived, &exercise.default_weight, &exercise.default_reps, &exercise.default_sets, &exercise.bodyweight, &exercise.pr_reps, &exercise.category, &exercise.equipment, &exercise.unilateral, &exercise.rest_seconds])?;
+            transaction.execute("DELETE FROM exercise_muscles WHERE exercise_id=$1;", &[&id])?;
+            for (muscle, is_primary) in &exercise.muscles {
+                transaction.execute("INSERT INTO exercise_muscles (exercise_id, muscle, is_primary) VALUES ($1, $2, $3);", &[&id, muscle, is_primary])?;
+            }
+            if let Some(row) = transaction.query_opt("SELECT e.name FROM exercise_aliases a JOIN exercises e ON e.id=a.exercise_id WHERE lower(a.alias)=lower($1) AND a.exercise_id<>$2;", &[&exercise.name, &id])? {
+                report.conflicts.push(format!("'{}' is also an alias of '{}'", exercise.name, row.get::<_, String>(0)));
+            }
+            for alias in &exercise.aliases {
+                match transaction.query_opt(Db::NAME_OF_OTHER_EXERCISE_QUERY, &[alias, &id])? {
+                    Some(row) => report.conflicts.push(format!("alias '{}' of '{}' isn't restored, it already names '{}'", alias, exercise.name, row.get::<_, String>(0))),
+                    None => {
+                        transaction.execute("INSERT INTO exercise_aliases (exercise_id, alias) VALUES ($1, $2) ON CONFLICT (alias) DO NOTHING;", &[&id, alias])?;
+                    },
+                }
+            }
+            exercise_ids.insert(exercise.id, id);
+        }
+
+        for session in &backup.sessions {
+            let session_id: i32 = transaction.query_one("INSERT INTO sessions (date, import_key) VALUES ($1, $2) returning id;", &[&session.date, &session.import_key])?.get(0);
+            report.sessions += 1;
+            for lift in &session.lifts {
+                let exercise_id = match exercise_ids.get(&lift.exercise_id) {
+                    Some(&id) => id,
+                    None => return Err(format!("lift {} refers to exercise {} which isn't in the backup", lift.id, lift.exercise_id).into()),
+                };
+                transaction.execute("INSERT INTO lifts (exercise_id, session_id, weight, unit, reps, sets, prescribed_weight, prescribed_reps, notes, rpe, rest_seconds) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11);",
+                    &[&exercise_id, &session_id, &lift.weight, &lift.unit, &lift.reps, &lift.sets, &lift.prescribed_weight, &lift.prescribed_reps, &lift.notes, &lift.rpe, &lift.rest_seconds])?;
+                report.lifts += 1;
+            }
+        }
+
+        // The PRs of the backup are recomputed rather than copied, so ones it lacks or got wrong are set right.
+        Db::transaction_recompute_prs(&mut transaction, &exercise_ids.values().copied().collect::<Vec<i32>>())?;
+        for (date, weight) in &backup.bodyweights {
+            transaction.execute("INSERT INTO bodyweights (date, weight) VALUES ($1, $2) ON CONFLICT (date) DO UPDATE SET weight=EXCLUDED.weight;", &[date, weight])?;
+        }
+        for (start_date, name) in &backup.blocks {
+            transaction.execute("INSERT INTO blocks (name, start_date) VALUES ($1, $2) ON CONFLICT (start_date) DO UPDATE SET name=EXCLUDED.name;", &[name, start_date])?;
+        }
+
+        transaction.commit()?;
+        Ok(report)
+    }
+}
@@ -0,0 +1,140 @@
+use std::env;
+
+use chrono::NaiveDate;
+use terminal_size::{terminal_size, Width};
+
+const HEIGHT: usize = 10;
+
+pub fn terminal_width() -> usize {
+    if let Some(columns) = env::var("COLUMNS").ok().and_then(|c| c.parse().ok()) {
+        return columns;
+    }
+    match terminal_size() {
+        Some((Width(width), _)) => width as usize,
+        None => 80,
+    }
+}
+
+// Line chart of the points over their date range, `width` characters wide including the y axis labels.
+pub fn line_chart(title: &str, points: &[(NaiveDate, f32)], width: usize, format_y: &dyn Fn(f32) -> String) -> Vec<String> {
+    let mut ret: Vec<String> = vec![String::from(title)];
+    let (first, last) = match (points.first(), points.last()) {
+        (Some(first), Some(last)) => (first.0, last.0),
+        _ => {ret.push(String::from("  [No data]")); return ret;},
+    };
+
+    let mut y_min = points.iter().map(|p| p.1).fold(f32::INFINITY, f32::min);
+    let mut y_max = points.iter().map(|p| p.1).fold(f32::NEG_INFINITY, f32::max);
+    if y_max - y_min < 0.01 {
+        y_min -= 1.0;
+        y_max += 1.0;
+    }
+    let labels = [format_y(y_max), format_y((y_max + y_min) / 2.0), format_y(y_min)];
+    let label_width = labels.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+    let plot_width = width.saturating_sub(label_width + 3).max(10);
+
+    let span = (last - first).num_days();
+    let column = |date: NaiveDate| -> usize {
+        match span {
+            0 => plot_width / 2,
+            _ => ((date - first).num_days() as usize * (plot_width - 1)) / span as usize,
+        }
+    };
+    let row = |y: f32| -> usize {
+        let from_bottom = ((y - y_min) / (y_max - y_min) * (HEIGHT - 1) as f32).round() as usize;
+        HEIGHT - 1 - from_bottom.min(HEIGHT - 1)
+    };
+
+    let mut grid = vec![vec![' '; plot_width]; HEIGHT];
+    for pair in points.windows(2) {
+        let (c0, c1) = (column(pair[0].0), column(pair[1].0));
+        let y_at = |c: usize| -> f32 {
+            match c1 - c0 {
+                0 => pair[1].1,
+                steps => pair[0].1 + (pair[1].1 - pair[0].1) * (c - c0) as f32 / steps as f32,
+            }
+        };
+        for c in c0..=c1 {
+            let (r0, r1) = (row(y_at(c)), row(y_at((c + 1).min(c1))));
+            for line in grid.iter_mut().take(r0.max(r1) + 1).skip(r0.min(r1)) {
+                line[c] = '·';
+            }
+        }
+    }
+    for point in points {
+        grid[row(point.1)][column(point.0)] = '●';
+    }
+
+    for (i, line) in grid.iter().enumerate() {
+        let label = match i {
+            0 => labels[0].as_str(),
+            _ if i == HEIGHT / 2 => labels[1].as_str(),
+            _ if i == HEIGHT - 1 => labels[2].as_str(),
+            _ => "",
+        };
+        let axis = if label.is_empty() {'│'} else {'┤'};
+        ret.push(format!("{:>w$} {}{}", label, axis, line.iter().collect::<String>(), w = label_width));
+    }
+    ret.push(format!("{:>w$} └{}", "", "─".repeat(plot_width), w = label_width));
+    let first_str = first.format("%d.%m.%y").to_string();
+    let last_str = last.format("%d.%m.%y").to_string();
+    match span {
+        0 => ret.push(format!("{:>w$}  {:^p$}", "", first_str, w = label_width, p = plot_width)),
+        _ => ret.push(format!("{:>w$}  {}{:>p$}", "", first_str, last_str, w = label_width, p = plot_width.saturating_sub(first_str.len()))),
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, day).unwrap()
+    }
+
+    fn chart(points: &[(NaiveDate, f32)]) -> Vec<String> {
+        line_chart("e1RM", points, 40, &|y| format!("{}", y))
+    }
+
+    // The plot rows, without the title, axis and dates.
+    fn rows(chart: &[String]) -> &[String] {
+        &chart[1..=HEIGHT]
+    }
+
+    #[test]
+    fn shows_no_data() {
+        assert_eq!(chart(&[]), vec!["e1RM", "  [No data]"]);
+    }
+
+    #[test]
+    fn centers_a_single_point() {
+        let chart = chart(&[(day(6), 100.0)]);
+        assert_eq!(chart.len(), HEIGHT + 3);
+        let points: Vec<(usize, &String)> = rows(&chart).iter().enumerate().filter(|(_, row)| row.contains('●')).collect();
+        assert_eq!(points.len(), 1);
+        assert_eq!(points[0].1.matches('●').count(), 1);
+        assert!(!chart.concat().contains('·'));
+        assert!(rows(&chart)[0].starts_with("101 ┤"));
+        assert!(rows(&chart)[HEIGHT - 1].starts_with(" 99 ┤"));
+        assert_eq!(chart[HEIGHT + 2].trim(), "06.03.24");
+    }
+
+    #[test]
+    fn draws_equal_values_on_one_row() {
+        let chart = chart(&[(day(1), 80.0), (day(4), 80.0), (day(8), 80.0)]);
+        let rows: Vec<&String> = rows(&chart).iter().filter(|row| row.contains('●')).collect();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].matches('●').count(), 3);
+        assert!(chart.iter().all(|line| line.chars().count() <= 40));
+        assert!(chart[HEIGHT + 2].trim().starts_with("01.03.24"));
+        assert!(chart[HEIGHT + 2].ends_with("08.03.24"));
+    }
+
+    #[test]
+    fn spans_the_values_from_bottom_to_top() {
+        let chart = chart(&[(day(1), 60.0), (day(8), 100.0)]);
+        assert!(rows(&chart)[HEIGHT - 1].contains("60 ┤●"));
+        assert!(rows(&chart)[0].ends_with('●'));
+    }
+}
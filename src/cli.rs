@@ -0,0 +1,457 @@
+use std::env;
+use std::ffi::OsString;
+use std::fmt;
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand, ValueEnum};
+use chrono::{Datelike, Duration, NaiveDate};
+use serde::Serialize;
+
+use liftsql::{DateOrder, Db, ExerciseFilter, HistoryLift, LiftEntry, NewLift, PrBoard, Unit};
+use liftsql::stats::{GroupStats, Period, StatsQuery, TonnageStats};
+
+use crate::{PlannedLift, Tui};
+
+// Exit codes of the subcommands. clap itself exits with 2 on invalid arguments.
+const EXIT_INVALID: u8 = 1;
+const EXIT_DATABASE: u8 = 3;
+
+// Part of every JSON output. Bumped when fields are renamed or removed, adding fields doesn't change it.
+const JSON_VERSION: u32 = 1;
+
+#[derive(Parser)]
+#[command(name = "liftsql", about = "Store your lifts in an SQL database. Without a subcommand the full-screen interface is started, or the line-based menu on dumb terminals.")]
+pub struct Cli {
+    #[arg(long, help = "Use the line-based menu even if the terminal could do full screen")]
+    pub line: bool,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+// Entries with a negative weight like "chin -10x8" look like flags to clap, so they're moved behind "--"
+// where everything is part of the entry. The rest of the entry keeps its order.
+pub fn args() -> Vec<OsString> {
+    let mut args: Vec<OsString> = env::args_os().collect();
+    // Only when "log" is the subcommand, elsewhere it may be an exercise or a note. Options before it take no values.
+    let log = match args.iter().skip(1).position(|arg| !arg.to_string_lossy().starts_with('-')) {
+        Some(index) if args[index + 1] == "log" => index + 1,
+        _ => return args,
+    };
+    let is_negative_lift = |arg: &OsString| arg.to_str().is_some_and(|arg| {
+        arg.strip_prefix('-').is_some_and(|arg| arg.starts_with(|c: char| c.is_ascii_digit() || c == '.') && arg.contains(['x', '×']))
+    });
+    let negative: Vec<OsString> = args[log..].iter().filter(|arg| is_negative_lift(arg)).cloned().collect();
+    if negative.is_empty() {
+        return args;
+    }
+    let mut rest = args.split_off(log);
+    rest.retain(|arg| !is_negative_lift(arg));
+    args.extend(rest);
+    if !args.iter().any(|arg| arg == "--") {
+        args.push(OsString::from("--"));
+    }
+    args.extend(negative);
+    args
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    #[command(about = "Log a lift into the session on that date, creating the session if there is none")]
+    Log {
+        #[arg(required = true, help = "Exercise name, alias or unique prefix, optionally followed by the lift, e.g. squat 100x5x3, bench 80x5,5,4, chin +10x8 @8 or dl 180x1 rpe9")]
+        entry: Vec<String>,
+        #[arg(short, long, help = "Weight, e.g. 100 or 225lb. Without a unit the display unit of the settings is used")]
+        weight: Option<String>,
+        #[arg(short, long)]
+        reps: Option<f32>,
+        #[arg(short, long)]
+        sets: Option<f32>,
+        #[arg(long)]
+        rpe: Option<f32>,
+        #[arg(short, long, help = "yyyy-mm-dd, dd.mm.[yyyy] or days ago. Defaults to today")]
+        date: Option<String>,
+        #[arg(short, long)]
+        notes: Option<String>,
+        #[arg(long, help = "Allow a date in the future, which is rejected as a likely typo otherwise")]
+        allow_future: bool,
+    },
+    #[command(about = "Show the PR board of an exercise, or of all exercises")]
+    Pr {
+        exercise: Option<String>,
+        #[arg(long, value_enum, default_value_t = Format::Text)]
+        format: Format,
+    },
+    #[command(about = "Show the training plan")]
+    Plan {
+        #[command(subcommand)]
+        command: PlanCommand,
+    },
+    #[command(about = "List logged lifts, oldest first")]
+    History {
+        exercise: Option<String>,
+        #[arg(long)]
+        from: Option<String>,
+        #[arg(long)]
+        to: Option<String>,
+        #[arg(short = 'n', long, default_value_t = 20, help = "Show only the latest lifts")]
+        limit: i64,
+        #[arg(long, value_enum, default_value_t = Format::Text)]
+        format: Format,
+    },
+    #[command(about = "Show tonnage, sets per muscle group, intensity and frequency")]
+    Stats {
+        #[arg(long, help = "Defaults to 12 weeks ago")]
+        from: Option<String>,
+        #[arg(long)]
+        to: Option<String>,
+        #[arg(short, long, value_parser = ["week", "month", "block"], default_value = "week")]
+        period: String,
+        #[arg(long, help = "Only exercises of a category, equipment, muscle group or 'unilateral'")]
+        filter: Option<String>,
+        #[arg(long, value_enum, default_value_t = Format::Text)]
+        format: Format,
+    },
+    #[command(about = "Manage exercises")]
+    Exercise {
+        #[command(subcommand)]
+        command: ExerciseCommand,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum PlanCommand {
+    #[command(about = "Show the prescriptions of a plan day")]
+    Show {
+        #[arg(short, long, help = "Plan day starting at 1. Defaults to the current day of the settings")]
+        day: Option<usize>,
+        #[arg(long, value_enum, default_value_t = Format::Text)]
+        format: Format,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ExerciseCommand {
+    #[command(about = "Add a new exercise")]
+    Add {
+        name: String,
+    },
+}
+
+#[derive(Serialize)]
+struct HistoryOutput<'a> {
+    version: u32,
+    lifts: &'a [HistoryLift],
+}
+
+#[derive(Serialize)]
+struct PrOutput<'a> {
+    version: u32,
+    boards: &'a [PrBoard],
+}
+
+#[derive(Serialize)]
+struct PlanOutput<'a> {
+    version: u32,
+    // Starting at 1 like the --day argument.
+    day: usize,
+    days: usize,
+    name: &'a str,
+    lifts: &'a [PlannedLift],
+}
+
+#[derive(Serialize)]
+struct StatsOutput<'a> {
+    version: u32,
+    from: NaiveDate,
+    to: NaiveDate,
+    period: Period,
+    tonnage: &'a [TonnageStats],
+    tonnage_by_category: &'a [GroupStats],
+    sets_per_muscle: &'a [GroupStats],
+    intensity: &'a [GroupStats],
+    frequency: &'a [GroupStats],
+}
+
+enum CliError {
+    // Bad input such as an unknown exercise or an invalid date.
+    Invalid(String),
+    Database(postgres::Error),
+}
+
+impl From<postgres::Error> for CliError {
+    fn from(err: postgres::Error) -> CliError {
+        CliError::Database(err)
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CliError::Invalid(msg) => write!(f, "{}", msg),
+            CliError::Database(err) => write!(f, "database error: {}", err),
+        }
+    }
+}
+
+pub fn run(command: Command) -> ExitCode {
+    let db = match Db::new("localhost", "postgres", &None, "liftsql") {
+        Ok(db) => db,
+        Err(err) => {
+            eprintln!("liftsql: couldn't connect to the database: {}", err);
+            return ExitCode::from(EXIT_DATABASE);
+        },
+    };
+    let mut tui = Tui::with_db(db);
+    let result = match command {
+        Command::Log {entry, weight, reps, sets, rpe, date, notes, allow_future} => {
+            let entry = LiftEntry::parse(&entry.join(" "));
+            let flags = LiftEntry {exercise: String::new(), weight: weight.as_deref().and_then(Unit::parse_weight), reps: reps.into_iter().collect(), sets, rpe};
+            match (entry, weight.is_some() && flags.weight.is_none()) {
+                (Some(entry), false) => log(&mut tui, entry, flags, date, notes, allow_future),
+                _ => Err(CliError::Invalid(String::from("invalid entry, use e.g. 'squat 100x5x3' or 'squat -w 100 -r 5 -s 3'"))),
+            }
+        },
+        Command::Pr {exercise, format} => pr(&mut tui, exercise, format),
+        Command::Plan {command: PlanCommand::Show {day, format}} => plan_show(&mut tui, day, format),
+        Command::History {exercise, from, to, limit, format} => history(&mut tui, exercise, from, to, limit, format),
+        Command::Stats {from, to, period, filter, format} => stats(&mut tui, from, to, &period, filter, format),
+        Command::Exercise {command: ExerciseCommand::Add {name}} => exercise_add(&mut tui, &name),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("liftsql: {}", err);
+            match err {
+                CliError::Invalid(_) => ExitCode::from(EXIT_INVALID),
+                CliError::Database(_) => ExitCode::from(EXIT_DATABASE),
+            }
+        },
+    }
+}
+
+fn find_exercise(tui: &mut Tui, name: &str) -> Result<(i32, String), CliError> {
+    let exercises = tui.db.select_exercises()?;
+    let aliases = tui.db.select_exercise_aliases()?;
+    let matching = Tui::match_name_to_exercise(name.to_string(), &exercises, &aliases);
+    match matching.len() {
+        1 => Ok(matching[0].clone()),
+        0 => Err(CliError::Invalid(Tui::get_no_match_string(name, &exercises, &aliases))),
+        _ => Err(CliError::Invalid(format!("'{}' matches several exercises: {}", name, Tui::get_matches_string(&matching)))),
+    }
+}
+
+// Accepts whatever the menu does, e.g. 2024-03-01, yesterday, mon, -1w or a date in the configured order.
+fn parse_date(s: &str, today: &NaiveDate, order: DateOrder) -> Result<NaiveDate, CliError> {
+    liftsql::parse_date(s, today, order).ok_or_else(|| CliError::Invalid(format!("invalid date '{}'", s)))
+}
+
+fn print_json<T: Serialize>(value: &T) -> Result<(), CliError> {
+    match serde_json::to_string_pretty(value) {
+        Ok(json) => {
+            println!("{}", json);
+            Ok(())
+        },
+        Err(err) => Err(CliError::Invalid(format!("couldn't write JSON: {}", err))),
+    }
+}
+
+// Flags fill in or override the parts of the one-line entry.
+fn log(tui: &mut Tui, mut entry: LiftEntry, flags: LiftEntry, date: Option<String>, notes: Option<String>, allow_future: bool) -> Result<(), CliError> {
+    entry.weight = flags.weight.or(entry.weight);
+    if !flags.reps.is_empty() {
+        entry.reps = flags.reps;
+    }
+    entry.sets = flags.sets.or(entry.sets);
+    entry.rpe = flags.rpe.or(entry.rpe);
+    if !entry.is_complete() {
+        return Err(CliError::Invalid(format!("'{}' is missing the weight or reps", entry.exercise)));
+    }
+    if entry.reps.len() > 1 && entry.sets.is_some() {
+        return Err(CliError::Invalid(String::from("a list of reps can't be combined with sets")));
+    }
+    if !entry.has_valid_counts() {
+        return Err(CliError::Invalid(String::from("reps must be above 0 and sets a whole number of at least 1")));
+    }
+    if let Some(rpe) = entry.rpe.filter(|rpe| !(1.0..=10.0).contains(rpe)) {
+        return Err(CliError::Invalid(format!("the RPE must be between 1 and 10, not {}", rpe)));
+    }
+
+    let exercise = find_exercise(tui, &entry.exercise)?;
+    let (weight, unit) = match entry.weight {
+        Some((weight, unit)) => (weight, unit.unwrap_or(tui.unit)),
+        None => return Err(CliError::Invalid(String::from("missing weight"))),
+    };
+    let today = tui.db.select_current_date()?;
+    let date = match date {
+        Some(date) => parse_date(&date, &today, tui.date_order)?,
+        None => today,
+    };
+    if date > today && !allow_future {
+        return Err(CliError::Invalid(format!("{} is in the future, add --allow-future to log on it anyway", date.format("%Y-%m-%d"))));
+    }
+    let display_unit = tui.unit;
+
+    let mut transaction = tui.db.transaction_start()?;
+    let session_id = match Db::transaction_select_session_on(&mut transaction, &date)? {
+        Some(id) => id,
+        None => Db::transaction_insert_session(&mut transaction, &date)?,
+    };
+    let mut lift_ids: Vec<i32> = Vec::new();
+    for (reps, sets) in entry.rep_groups() {
+        let lift = NewLift {exercise_id: exercise.0, weight, unit, reps, sets, prescribed_weight: None, prescribed_reps: None, notes: notes.clone(), rpe: entry.rpe, rest: None};
+        lift_ids.push(Db::transaction_insert_lift(&mut transaction, session_id, &lift)?);
+        println!("Logged {} {}x{}x{} on {}.", exercise.1, unit.format(unit.to_kg(weight)), reps, sets, date.format("%d.%m.%Y"));
+    }
+    let prs = Db::transaction_select_session_prs(&mut transaction, session_id)?;
+    Db::transaction_commit(transaction)?;
+
+    for pr in prs.iter().filter(|pr| lift_ids.contains(&pr.lift_id)) {
+        println!("*** {}", Tui::get_pr_string(pr, display_unit));
+    }
+    Ok(())
+}
+
+fn pr(tui: &mut Tui, exercise: Option<String>, format: Format) -> Result<(), CliError> {
+    let exercise_id = match exercise {
+        Some(name) => Some(find_exercise(tui, &name)?.0),
+        None => None,
+    };
+    let boards = tui.db.select_pr_board(exercise_id)?;
+    if format == Format::Json {
+        return print_json(&PrOutput {version: JSON_VERSION, boards: &boards});
+    }
+    tui.print_pr_boards(&boards);
+    Ok(())
+}
+
+fn plan_show(tui: &mut Tui, day: Option<usize>, format: Format) -> Result<(), CliError> {
+    let day_id = match day {
+        Some(day) if day >= 1 && day <= tui.plan.len() => day - 1,
+        Some(day) => return Err(CliError::Invalid(format!("the plan has days 1 to {}, not {}", tui.plan.len(), day))),
+        None => tui.read_config().map(|config| config.day_id as usize).unwrap_or(0),
+    };
+    if format == Format::Json {
+        return match tui.select_plan_day(day_id as i32)? {
+            Some((name, lifts)) => print_json(&PlanOutput {version: JSON_VERSION, day: day_id + 1, days: tui.plan.len(), name: &name, lifts: &lifts}),
+            None => Err(CliError::Invalid(format!("the plan has no day {}", day_id + 1))),
+        };
+    }
+    println!("Day {} of {}", day_id + 1, tui.plan.len());
+    tui.print_day(day_id as i32)?;
+    Ok(())
+}
+
+fn stats(tui: &mut Tui, from: Option<String>, to: Option<String>, period: &str, filter: Option<String>, format: Format) -> Result<(), CliError> {
+    let today = tui.db.select_current_date()?;
+    let filter = match filter {
+        Some(term) => match ExerciseFilter::parse(&term) {
+            Some(filter) => filter,
+            None => return Err(CliError::Invalid(format!("'{}' is no category, equipment, muscle group or 'unilateral'", term))),
+        },
+        None => ExerciseFilter::default(),
+    };
+    let stats = StatsQuery {
+        from: match from {
+            Some(from) => parse_date(&from, &today, tui.date_order)?,
+            None => today - Duration::weeks(12),
+        },
+        to: match to {
+            Some(to) => parse_date(&to, &today, tui.date_order)?,
+            None => today,
+        },
+        period: Period::parse(period).unwrap_or(Period::Week),
+        filter,
+    };
+    let tonnage = tui.db.select_tonnage(&stats)?;
+    let tonnage_by_category = tui.db.select_tonnage_by_category(&stats)?;
+    let sets_per_muscle = tui.db.select_sets_per_muscle(&stats)?;
+    let intensity = tui.db.select_intensity(&stats)?;
+    let frequency = tui.db.select_frequency(&stats)?;
+    if format == Format::Json {
+        return print_json(&StatsOutput {
+            version: JSON_VERSION, from: stats.from, to: stats.to, period: stats.period,
+            tonnage: &tonnage, tonnage_by_category: &tonnage_by_category, sets_per_muscle: &sets_per_muscle, intensity: &intensity, frequency: &frequency,
+        });
+    }
+
+    let unit = tui.unit;
+    let blocks = tui.db.select_blocks()?;
+    println!("== Tonnage ==");
+    if tonnage.is_empty() {
+        println!("[No lifts in range.]");
+    }
+    println!("{:<24} {:>12} {:>8} {:>9}", "Period", "Tonnage", "Sets", "Sessions");
+    for row in &tonnage {
+        println!("{:<24} {:>12} {:>8} {:>9}", Tui::get_period_string(&blocks, stats.period, &row.period), unit.format(row.tonnage), row.sets, row.sessions);
+    }
+    println!("== Tonnage per category ==");
+    tui.print_group_stats(&stats, &tonnage_by_category, |v| unit.format(v))?;
+    println!("== Sets per muscle group ==");
+    tui.print_group_stats(&stats, &sets_per_muscle, |v| format!("{} sets", v))?;
+    println!("== Intensity ==");
+    tui.print_group_stats(&stats, &intensity, |v| format!("{:.1}%", v))?;
+    println!("== Frequency ==");
+    tui.print_group_stats(&stats, &frequency, |v| format!("{}x", v))?;
+    Ok(())
+}
+
+fn history(tui: &mut Tui, exercise: Option<String>, from: Option<String>, to: Option<String>, limit: i64, format: Format) -> Result<(), CliError> {
+    if limit < 1 {
+        return Err(CliError::Invalid(format!("the number of lifts must be at least 1, not {}", limit)));
+    }
+    let exercise_id = match exercise {
+        Some(name) => Some(find_exercise(tui, &name)?.0),
+        None => None,
+    };
+    let today = tui.db.select_current_date()?;
+    let from = match from {
+        Some(from) => parse_date(&from, &today, tui.date_order)?,
+        None => NaiveDate::from_ymd_opt(1, 1, 1).unwrap_or(today),
+    };
+    let to = match to {
+        Some(to) => parse_date(&to, &today, tui.date_order)?,
+        None => today,
+    };
+    let lifts = tui.db.select_history(exercise_id, &from, &to, limit)?;
+    if format == Format::Json {
+        return print_json(&HistoryOutput {version: JSON_VERSION, lifts: &lifts});
+    }
+    if lifts.is_empty() {
+        println!("[No lifts found.]");
+    }
+    let mut session_id = None;
+    for lift in &lifts {
+        if session_id != Some(lift.session_id) {
+            println!("{} {}", lift.date.weekday(), lift.date.format("%d.%m.%Y"));
+            session_id = Some(lift.session_id);
+        }
+        // Bodyweight exercises are logged with the added weight, the load includes the bodyweight.
+        let (sign, load) = match lift.load != lift.weight {
+            true => (if lift.weight < 0.0 { "" } else { "+" }, format!(" (load {})", tui.unit.format(lift.load))),
+            false => ("", String::new()),
+        };
+        let rpe = lift.rpe.map(|rpe| format!(" @{}", rpe)).unwrap_or_default();
+        let notes = lift.notes.as_ref().map(|notes| format!(" ({})", notes)).unwrap_or_default();
+        println!("  {} {}{}x{}x{}{}{}{}", lift.exercise_name, sign, tui.unit.format(lift.weight), lift.reps, lift.sets, load, rpe, notes);
+    }
+    Ok(())
+}
+
+fn exercise_add(tui: &mut Tui, name: &str) -> Result<(), CliError> {
+    let name = name.trim();
+    if let Err(err) = liftsql::check_exercise_name(name) {
+        return Err(CliError::Invalid(err));
+    }
+    if tui.db.select_exercise_name_taken(name, None)? {
+        return Err(CliError::Invalid(format!("'{}' is already an exercise or alias", name)));
+    }
+    tui.db.insert_exercise(name.to_string())?;
+    println!("Added exercise {}.", name);
+    Ok(())
+}
@@ -0,0 +1,181 @@
+use chrono::{Datelike, Days, Duration, NaiveDate, Weekday};
+
+// Order of day, month and year in typed dates like 3.1., 1/3/2024 or 2024-1-3.
+#[derive(Clone, Copy, PartialEq)]
+pub enum DateOrder {
+    Dmy,
+    Mdy,
+    Ymd,
+}
+
+impl DateOrder {
+    pub fn parse(s: &str) -> Option<DateOrder> {
+        match s.to_lowercase().as_str() {
+            "dmy" => Some(DateOrder::Dmy),
+            "mdy" => Some(DateOrder::Mdy),
+            "ymd" => Some(DateOrder::Ymd),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DateOrder::Dmy => "dmy",
+            DateOrder::Mdy => "mdy",
+            DateOrder::Ymd => "ymd",
+        }
+    }
+
+    pub fn format(&self, date: &NaiveDate) -> String {
+        match self {
+            DateOrder::Dmy => date.format("%d.%m.%Y"),
+            DateOrder::Mdy => date.format("%m/%d/%Y"),
+            DateOrder::Ymd => date.format("%Y-%m-%d"),
+        }.to_string()
+    }
+
+    // Day, month and optional year of the parts of a typed date.
+    fn split<'a>(&self, parts: &[&'a str]) -> Option<(&'a str, &'a str, Option<&'a str>)> {
+        match (self, parts) {
+            (DateOrder::Dmy, [day, month]) => Some((day, month, None)),
+            (DateOrder::Dmy, [day, month, year]) => Some((day, month, Some(year))),
+            (DateOrder::Mdy, [month, day]) => Some((day, month, None)),
+            (DateOrder::Mdy, [month, day, year]) => Some((day, month, Some(year))),
+            (DateOrder::Ymd, [month, day]) => Some((day, month, None)),
+            (DateOrder::Ymd, [year, month, day]) => Some((day, month, Some(year))),
+            _ => None,
+        }
+    }
+}
+
+// Days ago like 3, -3, 3d, -3d or -1w. The minus is optional, dates in the future can't be typed this way.
+fn parse_days_ago(s: &str) -> Option<u64> {
+    let s = s.strip_prefix('-').unwrap_or(s);
+    let (number, days) = match s.strip_suffix('w') {
+        Some(weeks) => (weeks, 7),
+        None => (s.strip_suffix('d').unwrap_or(s), 1),
+    };
+    if number.is_empty() || !number.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    number.parse::<u64>().ok()?.checked_mul(days)
+}
+
+fn parse_numeric(s: &str, today: &NaiveDate, order: DateOrder) -> Option<NaiveDate> {
+    let separator = ['.', '/', '-'].into_iter().find(|c| s.contains(*c))?;
+    // Dates like 3.1. end with the separator.
+    let parts: Vec<&str> = s.strip_suffix(separator).unwrap_or(s).split(separator).collect();
+    let (day, month, year) = order.split(&parts)?;
+    let year = match year {
+        // A two-digit year is the latest one not after this year, so 99 is 1999.
+        Some(year) if year.len() == 2 => {
+            let year = today.year() / 100 * 100 + year.parse::<i32>().ok()?;
+            if year > today.year() { year - 100 } else { year }
+        }
+        Some(year) => year.parse::<i32>().ok()?,
+        None => today.year(),
+    };
+    NaiveDate::from_ymd_opt(year, month.parse().ok()?, day.parse().ok()?)
+}
+
+// Resolves a typed date against today: today, yesterday, weekday names for the most recent such day,
+// days ago (3, -3d, -1w), ISO dates and dates in the configured order, the year defaulting to this one.
+pub fn parse_date(s: &str, today: &NaiveDate, order: DateOrder) -> Option<NaiveDate> {
+    let s = s.trim().to_lowercase();
+    match s.as_str() {
+        "today" => return Some(*today),
+        "yesterday" => return Some(*today - Duration::days(1)),
+        _ => {},
+    }
+    if let Ok(weekday) = s.parse::<Weekday>() {
+        let days_ago = (today.weekday().num_days_from_monday() + 7 - weekday.num_days_from_monday()) % 7;
+        return Some(*today - Duration::days(days_ago.into()));
+    }
+    if let Some(days) = parse_days_ago(&s) {
+        return today.checked_sub_days(Days::new(days));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(&s, "%Y-%m-%d") {
+        return Some(date);
+    }
+    parse_numeric(&s, today, order)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A Wednesday.
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, 6).unwrap()
+    }
+
+    fn date(year: i32, month: u32, day: u32) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(year, month, day)
+    }
+
+    fn parse(s: &str) -> Option<NaiveDate> {
+        parse_date(s, &today(), DateOrder::Dmy)
+    }
+
+    #[test]
+    fn parses_today_and_yesterday() {
+        assert_eq!(parse("today"), date(2024, 3, 6));
+        assert_eq!(parse(" Yesterday "), date(2024, 3, 5));
+    }
+
+    #[test]
+    fn parses_weekdays_as_the_most_recent_one() {
+        assert_eq!(parse("wed"), date(2024, 3, 6));
+        assert_eq!(parse("Monday"), date(2024, 3, 4));
+        assert_eq!(parse("thu"), date(2024, 2, 29));
+    }
+
+    #[test]
+    fn parses_days_and_weeks_ago() {
+        assert_eq!(parse("0"), date(2024, 3, 6));
+        assert_eq!(parse("3"), date(2024, 3, 3));
+        assert_eq!(parse("-3"), date(2024, 3, 3));
+        assert_eq!(parse("-3d"), date(2024, 3, 3));
+        assert_eq!(parse("-1w"), date(2024, 2, 28));
+        assert_eq!(parse("2w"), date(2024, 2, 21));
+    }
+
+    #[test]
+    fn parses_iso_dates() {
+        assert_eq!(parse("2023-12-31"), date(2023, 12, 31));
+        assert_eq!(parse_date("2023-12-31", &today(), DateOrder::Mdy), date(2023, 12, 31));
+    }
+
+    #[test]
+    fn parses_dates_in_each_order() {
+        assert_eq!(parse_date("3.1.", &today(), DateOrder::Dmy), date(2024, 1, 3));
+        assert_eq!(parse_date("3.1.2023", &today(), DateOrder::Dmy), date(2023, 1, 3));
+        assert_eq!(parse_date("1/3", &today(), DateOrder::Mdy), date(2024, 1, 3));
+        assert_eq!(parse_date("1/3/2023", &today(), DateOrder::Mdy), date(2023, 1, 3));
+        assert_eq!(parse_date("1-3", &today(), DateOrder::Ymd), date(2024, 1, 3));
+        assert_eq!(parse_date("2023/1/3", &today(), DateOrder::Ymd), date(2023, 1, 3));
+    }
+
+    #[test]
+    fn parses_two_digit_years() {
+        assert_eq!(parse("3.1.23"), date(2023, 1, 3));
+        assert_eq!(parse("3.1.24"), date(2024, 1, 3));
+        assert_eq!(parse("3.1.25"), date(1925, 1, 3));
+        assert_eq!(parse_date("1/3/99", &today(), DateOrder::Mdy), date(1999, 1, 3));
+    }
+
+    #[test]
+    fn formats_in_each_order() {
+        let date = date(2024, 1, 3).unwrap();
+        assert_eq!(DateOrder::Dmy.format(&date), "03.01.2024");
+        assert_eq!(DateOrder::Mdy.format(&date), "01/03/2024");
+        assert_eq!(DateOrder::Ymd.format(&date), "2024-01-03");
+    }
+
+    #[test]
+    fn rejects_invalid_dates() {
+        for s in ["", "tomorrow", "abc", "+3", "-", "-d", "3x", "31.2.", "3.1.2.4", "1/13", "999999999999999", "-999999999999999w", "99999999999999999999"] {
+            assert_eq!(parse(s), None, "{}", s);
+        }
+    }
+}
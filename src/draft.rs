@@ -0,0 +1,72 @@
+use std::env;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{BufReader, Write};
+use std::path::PathBuf;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use liftsql::Db;
+
+use crate::PendingLift;
+
+const DRAFT_FILE: &str = ".config/.liftsql_draft.json";
+
+// The session being logged, written after every change so a dropped connection doesn't lose it.
+// It's removed once the session is logged or discarded.
+#[derive(Serialize, Deserialize)]
+pub struct Draft {
+    pub date: NaiveDate,
+    // In kg, logged together with the session.
+    pub bodyweight: Option<f32>,
+    pub lifts: Vec<PendingLift>,
+}
+
+fn draft_path() -> Result<PathBuf, Box<dyn Error>> {
+    let mut path = env::home_dir().ok_or("can't find the home directory")?;
+    path.push(DRAFT_FILE);
+    Ok(path)
+}
+
+impl Draft {
+    // A crash between logging the session and removing its draft leaves one behind that would log it twice.
+    pub fn logged(&self, db: &mut Db) -> Result<bool, postgres::Error> {
+        db.select_session_logged(&self.date, &self.lifts.iter().map(|pending| &pending.lift).collect::<Vec<_>>())
+    }
+
+    pub fn read() -> Result<Option<Draft>, Box<dyn Error>> {
+        let path = draft_path()?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_reader(BufReader::new(File::open(path)?))?))
+    }
+
+    // Written next to the draft first, so a crash while writing keeps the previous one.
+    fn write(&self) -> Result<(), Box<dyn Error>> {
+        let path = draft_path()?;
+        let tmp_path = path.with_extension("json.tmp");
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(serde_json::to_string(self)?.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(tmp_path, path)?;
+        Ok(())
+    }
+
+    // Nothing is worth resuming without lifts.
+    pub fn sync(&self) -> Result<(), Box<dyn Error>> {
+        match self.lifts.is_empty() {
+            true => Draft::remove(),
+            false => self.write(),
+        }
+    }
+
+    pub fn remove() -> Result<(), Box<dyn Error>> {
+        let path = draft_path()?;
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,170 @@
+use crate::Unit;
+
+// A lift typed on one line, e.g. "squat 100x5x3", "bench 80x5,5,4", "chin +10x8 @8" or "dl 180x1 rpe9".
+// Everything after the exercise is optional, so "squat" or "squat 100" are partial entries.
+#[derive(Default)]
+pub struct LiftEntry {
+    pub exercise: String,
+    pub weight: Option<(f32, Option<Unit>)>,
+    // One count per set when given as a list, otherwise the reps of every set.
+    pub reps: Vec<f32>,
+    pub sets: Option<f32>,
+    pub rpe: Option<f32>,
+}
+
+// The exercise name ends at the first token that starts like a weight or an RPE.
+fn is_spec(token: &str) -> bool {
+    let lower = token.to_lowercase();
+    token.starts_with(|c: char| c.is_ascii_digit() || c == '+' || c == '-' || c == '.' || c == '@') || lower == "rpe" || (lower.starts_with("rpe") && lower[3..].parse::<f32>().is_ok())
+}
+
+// Reps are positive, sets whole and at least one. NaN and infinity fail both.
+fn valid_reps(reps: f32) -> bool {
+    reps.is_finite() && reps > 0.0
+}
+
+fn valid_sets(sets: f32) -> bool {
+    sets.is_finite() && sets >= 1.0 && sets.fract() == 0.0
+}
+
+fn parse_rpe(s: &str) -> Option<f32> {
+    let rpe = s.trim().parse::<f32>().ok()?;
+    (1.0..=10.0).contains(&rpe).then_some(rpe)
+}
+
+impl LiftEntry {
+    pub fn parse(s: &str) -> Option<LiftEntry> {
+        let tokens: Vec<&str> = s.split_whitespace().collect();
+        let name_len = tokens.iter().position(|t| is_spec(t)).unwrap_or(tokens.len());
+        let mut entry = LiftEntry {exercise: tokens[..name_len].join(" "), ..LiftEntry::default()};
+
+        let mut rest = tokens[name_len..].iter();
+        while let Some(token) = rest.next() {
+            let lower = token.to_lowercase();
+            if let Some(rpe) = lower.strip_prefix('@').or(lower.strip_prefix("rpe")) {
+                // "@8" and "@ 8" both work.
+                entry.rpe = match rpe {
+                    "" => Some(parse_rpe(rest.next()?)?),
+                    rpe => Some(parse_rpe(rpe)?),
+                };
+            } else if entry.weight.is_none() {
+                // Weight, then reps, then sets. "+10" is added weight on a bodyweight exercise.
+                let mut parts = lower.split(['x', '×']);
+                let weight = parts.next()?;
+                entry.weight = Some(Unit::parse_weight(weight.strip_prefix('+').unwrap_or(weight))?);
+                if let Some(reps) = parts.next() {
+                    entry.reps = reps.split(',').map(|r| r.trim().parse::<f32>().ok().filter(|r| valid_reps(*r))).collect::<Option<Vec<f32>>>()?;
+                }
+                if let Some(sets) = parts.next() {
+                    // A list of reps already says how many sets there were.
+                    if entry.reps.len() > 1 {
+                        return None;
+                    }
+                    entry.sets = Some(sets.parse::<f32>().ok().filter(|s| valid_sets(*s))?);
+                }
+                if parts.next().is_some() {
+                    return None;
+                }
+            } else {
+                return None;
+            }
+        }
+        Some(entry)
+    }
+
+    // Whether reps and sets given as flags hold up like those of the entry line.
+    pub fn has_valid_counts(&self) -> bool {
+        self.reps.iter().all(|reps| valid_reps(*reps)) && self.sets.is_none_or(valid_sets)
+    }
+
+    pub fn is_complete(&self) -> bool {
+        !self.exercise.is_empty() && self.weight.is_some() && !self.reps.is_empty()
+    }
+
+    // (reps, sets) of the lifts to log. Consecutive sets with the same reps are one lift, so 5,5,4 is 5x2 and 4x1.
+    pub fn rep_groups(&self) -> Vec<(f32, f32)> {
+        if let [reps] = self.reps.as_slice() {
+            return vec![(*reps, self.sets.unwrap_or(1.0))];
+        }
+        let mut groups: Vec<(f32, f32)> = Vec::new();
+        for reps in &self.reps {
+            match groups.last_mut() {
+                Some(last) if last.0 == *reps => last.1 += 1.0,
+                _ => groups.push((*reps, 1.0)),
+            }
+        }
+        groups
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> LiftEntry {
+        LiftEntry::parse(s).unwrap()
+    }
+
+    #[test]
+    fn parses_weight_reps_and_sets() {
+        let entry = parse("front squat 100x5x3");
+        assert_eq!(entry.exercise, "front squat");
+        assert_eq!(entry.weight, Some((100.0, None)));
+        assert_eq!(entry.reps, vec![5.0]);
+        assert_eq!(entry.sets, Some(3.0));
+        assert!(entry.is_complete());
+        assert_eq!(entry.rep_groups(), vec![(5.0, 3.0)]);
+    }
+
+    #[test]
+    fn parses_partial_entries() {
+        let entry = parse("squat");
+        assert_eq!(entry.exercise, "squat");
+        assert!(!entry.is_complete());
+        let entry = parse("squat 100lb");
+        assert_eq!(entry.weight, Some((100.0, Some(Unit::Lb))));
+        assert!(!entry.is_complete());
+    }
+
+    #[test]
+    fn groups_lists_of_reps() {
+        let entry = parse("bench 80x5,5,4");
+        assert_eq!(entry.reps, vec![5.0, 5.0, 4.0]);
+        assert_eq!(entry.rep_groups(), vec![(5.0, 2.0), (4.0, 1.0)]);
+        assert_eq!(parse("bench 80x5,4,5").rep_groups(), vec![(5.0, 1.0), (4.0, 1.0), (5.0, 1.0)]);
+    }
+
+    #[test]
+    fn parses_added_and_negative_weight() {
+        assert_eq!(parse("chin +10x8").weight, Some((10.0, None)));
+        assert_eq!(parse("chin -10x8").weight, Some((-10.0, None)));
+        assert_eq!(parse("dip 20×8×3").sets, Some(3.0));
+    }
+
+    #[test]
+    fn parses_rpe() {
+        assert_eq!(parse("chin +10x8 @8").rpe, Some(8.0));
+        assert_eq!(parse("chin +10x8 @ 8").rpe, Some(8.0));
+        assert_eq!(parse("dl 180x1 rpe9").rpe, Some(9.0));
+        assert_eq!(parse("dl 180x1 RPE 9.5").rpe, Some(9.5));
+        assert!(LiftEntry::parse("dl 180x1 @11").is_none());
+        assert!(LiftEntry::parse("dl 180x1 @").is_none());
+    }
+
+    #[test]
+    fn rejects_invalid_reps_and_sets() {
+        for s in ["squat 100xnan", "squat 100xinf", "squat 100x-5", "squat 100x0x3", "squat 100x5x-2", "squat 100x5x0", "squat 100x5x2.5", "squat 100x5,0,5", "squat 1e40x5"] {
+            assert!(LiftEntry::parse(s).is_none(), "{}", s);
+        }
+        assert_eq!(parse("squat 100x2.5").reps, vec![2.5]);
+    }
+
+    #[test]
+    fn rejects_invalid_entries() {
+        assert!(LiftEntry::parse("bench 80x5,5,4x3").is_none());
+        assert!(LiftEntry::parse("bench 80x5x3x2").is_none());
+        assert!(LiftEntry::parse("bench 80x5 90x5").is_none());
+        assert!(LiftEntry::parse("bench 80xfive").is_none());
+        assert!(LiftEntry::parse("bench 80stone").is_none());
+    }
+}
@@ -0,0 +1,111 @@
+use std::error::Error;
+use std::io::Write;
+use postgres::fallible_iterator::FallibleIterator;
+use postgres::types::ToSql;
+use chrono::NaiveDate;
+
+use crate::{Db, ExerciseFilter, Unit};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ExportRows {
+    // One row per logged lift with a sets column.
+    Lift,
+    // A lift of 5x3 becomes three rows numbered 1 to 3.
+    Set,
+}
+
+impl ExportRows {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExportRows::Lift => "lift",
+            ExportRows::Set => "set",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<ExportRows> {
+        [ExportRows::Lift, ExportRows::Set].into_iter().find(|r| r.as_str() == s.to_lowercase())
+    }
+}
+
+pub struct ExportQuery {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub filter: ExerciseFilter,
+    // Empty for all exercises matching the filter.
+    pub exercise_ids: Vec<i32>,
+    pub unit: Unit,
+    pub rows: ExportRows,
+}
+
+// Weights are exported without the float noise of the unit conversion.
+fn format_weight(unit: Unit, kg: f32) -> String {
+    format!("{}", (unit.from_kg(kg) * 100.0).round() / 100.0)
+}
+
+impl Db {
+    // Writes sessions joined with their lifts as CSV and returns the number of rows written.
+    // Rows are streamed from the server, so the history is never loaded into memory as a whole.
+    pub fn export_csv<W: Write>(&mut self, export: &ExportQuery, writer: W) -> Result<usize, Box<dyn Error>> {
+        let (category, equipment, muscle, unilateral) = export.filter.params();
+        let query = format!("
+            SELECT l.date, l.session_id, e.name, l.weight, l.load, l.reps, l.sets, l.e1rm, lifts.prescribed_weight, lifts.prescribed_reps, lifts.rpe, lifts.notes, lifts.rest_seconds
+            FROM lift_loads l JOIN lifts ON lifts.id=l.id JOIN exercises e ON e.id=l.exercise_id
+            WHERE l.date BETWEEN $1 AND $2 AND {} AND (cardinality($7::INT[])=0 OR e.id=ANY($7))
+            ORDER BY l.date, l.session_id, l.id;", ExerciseFilter::sql(3));
+        let params: [&(dyn ToSql + Sync); 7] = [&export.from, &export.to, &category, &equipment, &muscle, &unilateral, &export.exercise_ids];
+        let mut rows = self.client.query_raw(query.as_str(), params)?;
+
+        let mut csv = csv::Writer::from_writer(writer);
+        let count_column = match export.rows {
+            ExportRows::Lift => "sets",
+            ExportRows::Set => "set",
+        };
+        csv.write_record(["date", "session", "exercise", "weight", "load", "unit", "reps", count_column, "e1rm", "prescribed_weight", "prescribed_reps", "rpe", "rest_seconds", "notes"])?;
+
+        let unit = export.unit;
+        let mut written = 0;
+        while let Some(row) = rows.next()? {
+            let date: NaiveDate = row.get(0);
+            let session_id: i32 = row.get(1);
+            let name: String = row.get(2);
+            let sets: f32 = row.get(6);
+            let prescribed_weight: Option<f32> = row.get(8);
+            let prescribed_reps: Option<f32> = row.get(9);
+            let rpe: Option<f32> = row.get(10);
+            let notes: Option<String> = row.get(11);
+            let rest: Option<i32> = row.get(12);
+            let mut record = vec![
+                date.format("%Y-%m-%d").to_string(),
+                session_id.to_string(),
+                name,
+                format_weight(unit, row.get(3)),
+                format_weight(unit, row.get(4)),
+                unit.as_str().to_string(),
+                row.get::<_, f32>(5).to_string(),
+                String::new(),
+                format_weight(unit, row.get(7)),
+                prescribed_weight.map(|w| format_weight(unit, w)).unwrap_or_default(),
+                prescribed_reps.map(|r| r.to_string()).unwrap_or_default(),
+                rpe.map(|r| r.to_string()).unwrap_or_default(),
+                rest.map(|r| r.to_string()).unwrap_or_default(),
+                notes.unwrap_or_default(),
+            ];
+            match export.rows {
+                ExportRows::Lift => {
+                    record[7] = sets.to_string();
+                    csv.write_record(&record)?;
+                    written += 1;
+                },
+                ExportRows::Set => {
+                    for set in 1..=(sets.ceil() as i32).max(1) {
+                        record[7] = set.to_string();
+                        csv.write_record(&record)?;
+                        written += 1;
+                    }
+                },
+            }
+        }
+        csv.flush()?;
+        Ok(written)
+    }
+}
@@ -0,0 +1,62 @@
+use postgres::Error;
+use chrono::NaiveDate;
+use serde::Serialize;
+
+use crate::{Db, Unit};
+
+// One exercise in one session. Weights are total load in kg.
+pub struct SessionProgress {
+    pub date: NaiveDate,
+    pub top_load: f32,
+    pub e1rm: f32,
+    pub volume: f32,
+}
+
+// A logged lift with its session. The weight is in kg, `unit` is the one it was entered in.
+// For bodyweight exercises the weight is the added one and `load` includes the bodyweight.
+#[derive(Serialize)]
+pub struct HistoryLift {
+    pub session_id: i32,
+    pub date: NaiveDate,
+    pub exercise_name: String,
+    pub weight: f32,
+    pub load: f32,
+    pub unit: Unit,
+    pub reps: f32,
+    pub sets: f32,
+    pub notes: Option<String>,
+    pub rpe: Option<f32>,
+    pub rest_seconds: Option<i32>,
+}
+
+impl Db {
+    // The latest `limit` lifts in the range, oldest first.
+    pub fn select_history(&mut self, exercise_id: Option<i32>, from: &NaiveDate, to: &NaiveDate, limit: i64) -> Result<Vec<HistoryLift>, Error> {
+        let query = self.client.query("SELECT l.session_id, s.date, e.name, l.weight, l.unit, l.reps, l.sets, l.notes, l.rpe, ll.load, l.rest_seconds FROM lifts l JOIN sessions s ON s.id=l.session_id JOIN exercises e ON e.id=l.exercise_id JOIN lift_loads ll ON ll.id=l.id WHERE ($1::INT IS NULL OR l.exercise_id=$1) AND s.date BETWEEN $2 AND $3 ORDER BY s.date DESC, l.session_id DESC, l.id DESC LIMIT $4;", &[&exercise_id, &from, &to, &limit])?;
+        let mut ret: Vec<HistoryLift> = Vec::new();
+        for row in query.iter().rev() {
+            let unit: String = row.get(4);
+            ret.push(HistoryLift {session_id: row.get(0), date: row.get(1), exercise_name: row.get(2), weight: row.get(3), load: row.get(9), unit: Unit::parse(&unit).unwrap_or(Unit::Kg), reps: row.get(5), sets: row.get(6), notes: row.get(7), rpe: row.get(8), rest_seconds: row.get(10)});
+        }
+        Ok(ret)
+    }
+
+    pub fn select_exercise_progress(&mut self, exercise_id: i32, from: &NaiveDate, to: &NaiveDate) -> Result<Vec<SessionProgress>, Error> {
+        let query = self.client.query("SELECT date, MAX(load), MAX(e1rm), SUM(load*reps*sets)::REAL FROM lift_loads WHERE exercise_id=$1 AND date BETWEEN $2 AND $3 GROUP BY session_id, date ORDER BY date, session_id;", &[&exercise_id, &from, &to])?;
+        let mut ret: Vec<SessionProgress> = Vec::new();
+        for row in query {
+            ret.push(SessionProgress {date: row.get(0), top_load: row.get(1), e1rm: row.get(2), volume: row.get(3)});
+        }
+        Ok(ret)
+    }
+
+    // (monday of the week, load x reps x sets) for every week with lifts of the exercise.
+    pub fn select_exercise_weekly_volume(&mut self, exercise_id: i32, from: &NaiveDate, to: &NaiveDate) -> Result<Vec<(NaiveDate, f32)>, Error> {
+        let query = self.client.query("SELECT date_trunc('week', date)::DATE AS week, SUM(load*reps*sets)::REAL FROM lift_loads WHERE exercise_id=$1 AND date BETWEEN $2 AND $3 GROUP BY week ORDER BY week;", &[&exercise_id, &from, &to])?;
+        let mut ret: Vec<(NaiveDate, f32)> = Vec::new();
+        for row in query {
+            ret.push((row.get(0), row.get(1)));
+        }
+        Ok(ret)
+    }
+}
@@ -0,0 +1,349 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::io::Read;
+use chrono::{NaiveDate, NaiveDateTime};
+
+use crate::{check_exercise_name, Db, NewLift, Unit};
+
+// Tried in order when no date format is given, so day-first dates win over US month-first ones.
+pub const DATE_FORMATS: [&str; 6] = ["%Y-%m-%d", "%d.%m.%Y", "%d/%m/%Y", "%m/%d/%Y", "%Y/%m/%d", "%d-%m-%Y"];
+
+// Date, exercise, the bits of the weight in kg, reps and sets, and the notes of a lift.
+type LiftKey = (NaiveDate, i32, u32, u32, u32, Option<String>);
+
+// Header names of the CSV columns. Sets and notes are optional, a missing sets column means one set per row.
+#[derive(Clone)]
+pub struct ColumnMapping {
+    pub date: String,
+    pub exercise: String,
+    pub weight: String,
+    pub reps: String,
+    pub sets: Option<String>,
+    pub notes: Option<String>,
+}
+
+impl Default for ColumnMapping {
+    fn default() -> ColumnMapping {
+        ColumnMapping {
+            date: String::from("date"),
+            exercise: String::from("exercise"),
+            weight: String::from("weight"),
+            reps: String::from("reps"),
+            sets: Some(String::from("sets")),
+            notes: Some(String::from("notes")),
+        }
+    }
+}
+
+pub struct ImportOptions {
+    pub mapping: ColumnMapping,
+    // A chrono format string, or None to try DATE_FORMATS.
+    pub date_format: Option<String>,
+    // Unit of weights written without one, e.g. "100" instead of "100kg".
+    pub unit: Unit,
+    // Exercises that match neither a name nor an alias are created instead of reported.
+    pub create_exercises: bool,
+    pub dry_run: bool,
+}
+
+#[derive(Default)]
+pub struct ImportReport {
+    pub rows: usize,
+    pub lifts: usize,
+    pub sessions_created: usize,
+    pub sessions_reused: usize,
+    // Sessions imported from the same app export before.
+    pub sessions_skipped: usize,
+    // Rows that aren't lifts, e.g. cardio or warmup sets of another app.
+    pub rows_skipped: usize,
+    // Rows without a workout of another app that match a lift already logged on their date, e.g. when a file is imported again.
+    pub lifts_skipped: usize,
+    // Exercise names of another app and the exercise they were mapped to.
+    pub mapped_exercises: Vec<(String, String)>,
+    pub created_exercises: Vec<String>,
+    pub unknown_exercises: Vec<String>,
+    // Line number in the file and what was wrong with it.
+    pub errors: Vec<(u64, String)>,
+    pub committed: bool,
+}
+
+pub(crate) struct ImportRow {
+    pub(crate) date: NaiveDate,
+    // Identifies a workout of another app, so it isn't imported twice. Without it rows are grouped by date.
+    pub(crate) session_key: Option<String>,
+    pub(crate) exercise: String,
+    pub(crate) weight: f32,
+    pub(crate) unit: Unit,
+    pub(crate) reps: f32,
+    pub(crate) sets: f32,
+    pub(crate) notes: Option<String>,
+    pub(crate) rpe: Option<f32>,
+}
+
+pub fn parse_date(s: &str, format: Option<&str>) -> Option<NaiveDate> {
+    let formats: Vec<&str> = match format {
+        Some(format) => vec![format],
+        None => DATE_FORMATS.to_vec(),
+    };
+    for format in formats {
+        if let Ok(date) = NaiveDate::parse_from_str(s, format) {
+            return Some(date);
+        }
+        // Exports of other apps often carry a time, e.g. "2023-04-01 18:30:00".
+        if let Ok(datetime) = NaiveDateTime::parse_from_str(s, format!("{} %H:%M:%S", format).as_str()) {
+            return Some(datetime.date());
+        }
+        if let Ok(datetime) = NaiveDateTime::parse_from_str(s, format!("{} %H:%M", format).as_str()) {
+            return Some(datetime.date());
+        }
+    }
+    None
+}
+
+fn find_column(headers: &csv::StringRecord, name: &str) -> Result<usize, Box<dyn Error>> {
+    match headers.iter().position(|header| header.trim().eq_ignore_ascii_case(name.trim())) {
+        Some(index) => Ok(index),
+        None => Err(format!("column '{}' not found in the header", name).into()),
+    }
+}
+
+fn parse_row(record: &csv::StringRecord, columns: &[Option<usize>; 6], options: &ImportOptions) -> Result<ImportRow, String> {
+    let field = |column: Option<usize>| column.and_then(|index| record.get(index)).map(|f| f.trim()).unwrap_or("");
+    let date = field(columns[0]);
+    let date = parse_date(date, options.date_format.as_deref()).ok_or(format!("invalid date '{}'", date))?;
+    let exercise = field(columns[1]);
+    if exercise.is_empty() {
+        return Err(String::from("missing exercise"));
+    }
+    // An empty weight is a bodyweight exercise without added load.
+    let (weight, unit) = match field(columns[2]) {
+        "" => (0.0, None),
+        weight => Unit::parse_weight(weight).ok_or(format!("invalid weight '{}'", weight))?,
+    };
+    let reps = field(columns[3]);
+    let reps = reps.parse::<f32>().map_err(|_| format!("invalid reps '{}'", reps))?;
+    let sets = match field(columns[4]) {
+        "" => 1.0,
+        sets => sets.parse::<f32>().map_err(|_| format!("invalid sets '{}'", sets))?,
+    };
+    let notes = match field(columns[5]) {
+        "" => None,
+        notes => Some(notes.to_string()),
+    };
+    Ok(ImportRow {date, session_key: None, exercise: exercise.to_string(), weight, unit: unit.unwrap_or(options.unit), reps, sets, notes, rpe: None})
+}
+
+impl Db {
+    // Imports lifts from CSV in one transaction. Every date gets one session, reusing a session already
+    // logged on that date. The transaction is only committed when every row was valid and this isn't a dry run,
+    // so a dry run reports exactly what an import would do.
+    pub fn import_csv<R: Read>(&mut self, reader: R, options: &ImportOptions) -> Result<ImportReport, Box<dyn Error>> {
+        let mut report = ImportReport::default();
+        let mut csv = csv::ReaderBuilder::new().flexible(true).from_reader(reader);
+        let headers = csv.headers()?.clone();
+        let mapping = &options.mapping;
+        let columns = [
+            Some(find_column(&headers, &mapping.date)?),
+            Some(find_column(&headers, &mapping.exercise)?),
+            Some(find_column(&headers, &mapping.weight)?),
+            Some(find_column(&headers, &mapping.reps)?),
+            mapping.sets.as_ref().and_then(|name| find_column(&headers, name).ok()),
+            mapping.notes.as_ref().and_then(|name| find_column(&headers, name).ok()),
+        ];
+
+        let mut rows: Vec<(u64, ImportRow)> = Vec::new();
+        for record in csv.records() {
+            let record = record?;
+            let line = record.position().map(|p| p.line()).unwrap_or(0);
+            report.rows += 1;
+            match parse_row(&record, &columns, options) {
+                Ok(row) => rows.push((line, row)),
+                Err(err) => report.errors.push((line, err)),
+            }
+        }
+        self.import_rows(rows, options.create_exercises, options.dry_run, &mut report)?;
+        Ok(report)
+    }
+
+    pub(crate) fn import_rows(&mut self, mut rows: Vec<(u64, ImportRow)>, create_exercises: bool, dry_run: bool, report: &mut ImportReport) -> Result<(), Box<dyn Error>> {
+        // Oldest first, so new sessions get their IDs in date order.
+        rows.sort_by_key(|row| row.1.date);
+
+        let mut transaction = self.client.transaction()?;
+        let mut names: Vec<(i32, String)> = Vec::new();
+        for row in transaction.query("SELECT id, name FROM exercises UNION ALL SELECT exercise_id, alias FROM exercise_aliases;", &[])? {
+            names.push((row.get(0), row.get::<_, String>(1).to_lowercase()));
+        }
+        let mut sessions: Vec<(NaiveDate, Option<String>, Option<i32>)> = Vec::new();
+        let mut exercise_ids: Vec<i32> = Vec::new();
+        // How many more rows can still match lifts that were logged before the import, by date, exercise,
+        // weight, reps, sets and notes. Counted so that repeated identical sets within the file are kept.
+        let mut existing: HashMap<LiftKey, i64> = HashMap::new();
+        for (line, row) in rows {
+            let name = row.exercise.to_lowercase();
+            let exercise_id = match names.iter().find(|n| n.1 == name) {
+                Some(n) => n.0,
+                None if create_exercises => {
+                    if let Err(err) = check_exercise_name(&row.exercise) {
+                        report.errors.push((line, format!("can't create exercise '{}': {}", row.exercise, err)));
+                        continue;
+                    }
+                    let id: i32 = transaction.query_one("INSERT INTO exercises (name) VALUES ($1) returning id;", &[&row.exercise])?.get(0);
+                    names.push((id, name));
+                    report.created_exercises.push(row.exercise.clone());
+                    id
+                },
+                None => {
+                    if !report.unknown_exercises.iter().any(|e| e.to_lowercase() == name) {
+                        report.unknown_exercises.push(row.exercise.clone());
+                    }
+                    report.errors.push((line, format!("unknown exercise '{}'", row.exercise)));
+                    continue;
+                },
+            };
+            let session_id = match sessions.iter().find(|s| s.0 == row.date && s.1 == row.session_key) {
+                Some(s) => s.2,
+                None => {
+                    let id: Option<i32> = match &row.session_key {
+                        Some(key) => match transaction.query_opt("SELECT id FROM sessions WHERE import_key=$1;", &[key])? {
+                            Some(_) => {
+                                report.sessions_skipped += 1;
+                                None
+                            },
+                            None => {
+                                report.sessions_created += 1;
+                                Some(transaction.query_one("INSERT INTO sessions (date, import_key) VALUES ($1, $2) returning id;", &[&row.date, key])?.get(0))
+                            },
+                        },
+                        None => match transaction.query_opt("SELECT id FROM sessions WHERE date=$1 ORDER BY id LIMIT 1;", &[&row.date])? {
+                            Some(existing) => {
+                                report.sessions_reused += 1;
+                                Some(existing.get(0))
+                            },
+                            None => {
+                                report.sessions_created += 1;
+                                Some(transaction.query_one("INSERT INTO sessions (date) VALUES ($1) returning id;", &[&row.date])?.get(0))
+                            },
+                        },
+                    };
+                    sessions.push((row.date, row.session_key.clone(), id));
+                    id
+                },
+            };
+            // Already imported.
+            let session_id = match session_id {
+                Some(id) => id,
+                None => continue,
+            };
+            let lift = NewLift {exercise_id, weight: row.weight, unit: row.unit, reps: row.reps, sets: row.sets, prescribed_weight: None, prescribed_reps: None, notes: row.notes, rpe: row.rpe, rest: None};
+            if row.session_key.is_none() {
+                let weight = lift.unit.to_kg(lift.weight);
+                let key = (row.date, exercise_id, weight.to_bits(), lift.reps.to_bits(), lift.sets.to_bits(), lift.notes.clone());
+                let remaining = match existing.get_mut(&key) {
+                    Some(remaining) => remaining,
+                    None => {
+                        let count: i64 = transaction.query_one("SELECT COUNT(*) FROM lifts l JOIN sessions s ON s.id=l.session_id WHERE s.date=$1 AND l.exercise_id=$2 AND l.weight=$3 AND l.reps=$4 AND l.sets=$5 AND l.notes IS NOT DISTINCT FROM $6;",
+                            &[&row.date, &exercise_id, &weight, &lift.reps, &lift.sets, &lift.notes])?.get(0);
+                        existing.entry(key).or_insert(count)
+                    },
+                };
+                if *remaining > 0 {
+                    *remaining -= 1;
+                    report.lifts_skipped += 1;
+                    continue;
+                }
+            }
+            Db::transaction_insert_lift_without_prs(&mut transaction, session_id, &lift)?;
+            if !exercise_ids.contains(&exercise_id) {
+                exercise_ids.push(exercise_id);
+            }
+            report.lifts += 1;
+        }
+        // Imported lifts may be older than the existing history, so PRs are redone for whole exercises.
+        Db::transaction_recompute_prs(&mut transaction, &exercise_ids)?;
+
+        report.errors.sort_by_key(|e| e.0);
+        if !dry_run && report.errors.is_empty() {
+            transaction.commit()?;
+            report.committed = true;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(year, month, day)
+    }
+
+    fn options(unit: Unit) -> ImportOptions {
+        ImportOptions {mapping: ColumnMapping::default(), date_format: None, unit, create_exercises: false, dry_run: true}
+    }
+
+    const COLUMNS: [Option<usize>; 6] = [Some(0), Some(1), Some(2), Some(3), Some(4), Some(5)];
+
+    fn row(fields: &[&str]) -> Result<ImportRow, String> {
+        parse_row(&csv::StringRecord::from(fields.to_vec()), &COLUMNS, &options(Unit::Lb))
+    }
+
+    #[test]
+    fn parses_dates_day_first() {
+        assert_eq!(parse_date("2024-03-06", None), date(2024, 3, 6));
+        assert_eq!(parse_date("06.03.2024", None), date(2024, 3, 6));
+        assert_eq!(parse_date("06/03/2024", None), date(2024, 3, 6));
+        assert_eq!(parse_date("03/13/2024", None), date(2024, 3, 13));
+        assert_eq!(parse_date("2024/03/06", None), date(2024, 3, 6));
+        assert_eq!(parse_date("06-03-2024", None), date(2024, 3, 6));
+    }
+
+    #[test]
+    fn parses_dates_with_a_time() {
+        assert_eq!(parse_date("2023-04-01 18:30:00", None), date(2023, 4, 1));
+        assert_eq!(parse_date("01.04.2023 18:30", None), date(2023, 4, 1));
+    }
+
+    #[test]
+    fn parses_dates_in_the_given_format_only() {
+        assert_eq!(parse_date("6 Mar 2024", Some("%d %b %Y")), date(2024, 3, 6));
+        assert_eq!(parse_date("03/06/2024", Some("%m/%d/%Y")), date(2024, 3, 6));
+        assert_eq!(parse_date("2024-03-06", Some("%d.%m.%Y")), None);
+    }
+
+    #[test]
+    fn rejects_invalid_dates() {
+        assert_eq!(parse_date("yesterday", None), None);
+        assert_eq!(parse_date("31.02.2024", None), None);
+        assert_eq!(parse_date("", None), None);
+    }
+
+    #[test]
+    fn parses_rows() {
+        let row = row(&["2024-03-06", " Squat ", "100kg", "5", "3", "felt easy"]).unwrap();
+        assert_eq!(row.date, NaiveDate::from_ymd_opt(2024, 3, 6).unwrap());
+        assert_eq!(row.exercise, "Squat");
+        assert_eq!((row.weight, row.unit, row.reps, row.sets), (100.0, Unit::Kg, 5.0, 3.0));
+        assert_eq!(row.notes.as_deref(), Some("felt easy"));
+        assert!(row.session_key.is_none() && row.rpe.is_none());
+    }
+
+    #[test]
+    fn fills_in_optional_fields() {
+        let row = row(&["2024-03-06", "Chinups", "", "8", "", ""]).unwrap();
+        assert_eq!((row.weight, row.reps, row.sets), (0.0, 8.0, 1.0));
+        assert!(row.notes.is_none());
+        let row = parse_row(&csv::StringRecord::from(vec!["2024-03-06", "Bench", "185", "5"]), &[Some(0), Some(1), Some(2), Some(3), None, None], &options(Unit::Lb)).unwrap();
+        assert_eq!((row.weight, row.unit, row.sets), (185.0, Unit::Lb, 1.0));
+    }
+
+    #[test]
+    fn rejects_invalid_rows() {
+        assert_eq!(row(&["06.13.2024", "Squat", "100", "5", "1", ""]).err().unwrap(), "invalid date '06.13.2024'");
+        assert_eq!(row(&["2024-03-06", " ", "100", "5", "1", ""]).err().unwrap(), "missing exercise");
+        assert_eq!(row(&["2024-03-06", "Squat", "heavy", "5", "1", ""]).err().unwrap(), "invalid weight 'heavy'");
+        assert_eq!(row(&["2024-03-06", "Squat", "100", "five", "1", ""]).err().unwrap(), "invalid reps 'five'");
+        assert_eq!(row(&["2024-03-06", "Squat", "100", "5", "x", ""]).err().unwrap(), "invalid sets 'x'");
+    }
+}
@@ -1,10 +1,253 @@
-use postgres::{Client, NoTls, Error, Transaction};
+use postgres::{Client, NoTls, Error, Row, Transaction};
 use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+pub mod analysis;
+pub mod apps;
+pub mod backup;
+mod dates;
+mod entry;
+pub mod export;
+pub mod import;
+mod history;
+mod records;
+pub mod stats;
+
+pub use dates::{parse_date, DateOrder};
+pub use entry::LiftEntry;
+pub use history::{HistoryLift, SessionProgress};
+pub use records::{BoardValue, PersonalRecord, PrBoard, PrKind, RepMax};
 
 pub struct Db {
     client: Client,
 }
 
+// Rules for the names of new exercises and aliases, wherever they come from. Taken names are checked separately.
+pub fn check_exercise_name(name: &str) -> Result<(), String> {
+    let name = name.trim();
+    if name.chars().count() < 2 {
+        return Err(String::from("the name must be at least 2 characters long"));
+    }
+    // Typed at a prompt these cancel it.
+    if name.eq_ignore_ascii_case("q") || name.eq_ignore_ascii_case("c") {
+        return Err(format!("'{}' can't be used as a name", name));
+    }
+    Ok(())
+}
+
+// Epley estimate of the one rep max. Same formula as the e1rm column of the lift_loads view.
+pub fn e1rm(load: f32, reps: f32) -> f32 {
+    if reps < 1.0 {
+        0.0
+    } else if reps == 1.0 {
+        load
+    } else {
+        load * (1.0 + reps / 30.0)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Unit {
+    Kg,
+    Lb,
+}
+
+impl Unit {
+    const LB_PER_KG: f32 = 2.204_622_6;
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Unit::Kg => "kg",
+            Unit::Lb => "lb",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Unit> {
+        match s.to_lowercase().as_str() {
+            "kg" | "kgs" => Some(Unit::Kg),
+            "lb" | "lbs" => Some(Unit::Lb),
+            _ => None,
+        }
+    }
+
+    // Parses "100", "100kg" or "225 lb". The unit is None when it wasn't given.
+    pub fn parse_weight(s: &str) -> Option<(f32, Option<Unit>)> {
+        let s = s.trim();
+        let split = s.find(|c: char| c.is_alphabetic()).unwrap_or(s.len());
+        let value = s[..split].trim().parse::<f32>().ok().filter(|value| value.is_finite())?;
+        match s[split..].trim() {
+            "" => Some((value, None)),
+            unit => Some((value, Some(Unit::parse(unit)?))),
+        }
+    }
+
+    pub fn to_kg(&self, weight: f32) -> f32 {
+        match self {
+            Unit::Kg => weight,
+            Unit::Lb => weight / Unit::LB_PER_KG,
+        }
+    }
+
+    pub fn from_kg(&self, kg: f32) -> f32 {
+        match self {
+            Unit::Kg => kg,
+            Unit::Lb => kg * Unit::LB_PER_KG,
+        }
+    }
+
+    // Smallest jump that can be loaded on a bar: 1.25kg or 2.5lb plates on both sides.
+    pub fn plate_increment(&self) -> f32 {
+        match self {
+            Unit::Kg => 2.5,
+            Unit::Lb => 5.0,
+        }
+    }
+
+    pub fn round_to_plates(&self, weight: f32) -> f32 {
+        (weight / self.plate_increment()).round() * self.plate_increment()
+    }
+
+    // Converts a stored kg weight for display, e.g. "102.5kg" or "225lb".
+    pub fn format(&self, kg: f32) -> String {
+        format!("{}{}", (self.from_kg(kg) * 100.0).round() / 100.0, self.as_str())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Category {
+    Squat,
+    Hinge,
+    Push,
+    Pull,
+    Olympic,
+    Accessory,
+}
+
+impl Category {
+    pub const ALL: [Category; 6] = [Category::Squat, Category::Hinge, Category::Push, Category::Pull, Category::Olympic, Category::Accessory];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Category::Squat => "squat",
+            Category::Hinge => "hinge",
+            Category::Push => "push",
+            Category::Pull => "pull",
+            Category::Olympic => "olympic",
+            Category::Accessory => "accessory",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Category> {
+        Category::ALL.into_iter().find(|c| c.as_str() == s.to_lowercase())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Equipment {
+    Barbell,
+    Dumbbell,
+    Machine,
+    Bodyweight,
+}
+
+impl Equipment {
+    pub const ALL: [Equipment; 4] = [Equipment::Barbell, Equipment::Dumbbell, Equipment::Machine, Equipment::Bodyweight];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Equipment::Barbell => "barbell",
+            Equipment::Dumbbell => "dumbbell",
+            Equipment::Machine => "machine",
+            Equipment::Bodyweight => "bodyweight",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Equipment> {
+        Equipment::ALL.into_iter().find(|e| e.as_str() == s.to_lowercase())
+    }
+}
+
+pub const MUSCLE_GROUPS: [&str; 14] = ["Quads", "Hamstrings", "Glutes", "Lower back", "Chest", "Shoulders", "Triceps", "Biceps", "Lats", "Upper back", "Traps", "Core", "Forearms", "Calves"];
+
+pub struct ExerciseAttributes {
+    pub category: Option<Category>,
+    pub equipment: Option<Equipment>,
+    pub unilateral: bool,
+    pub primary_muscles: Vec<String>,
+    pub secondary_muscles: Vec<String>,
+}
+
+#[derive(Default)]
+pub struct ExerciseFilter {
+    pub category: Option<Category>,
+    pub equipment: Option<Equipment>,
+    pub muscle: Option<String>,
+    pub unilateral: Option<bool>,
+}
+
+impl ExerciseFilter {
+    // Accepts a category, an equipment, a muscle group or "unilateral".
+    pub fn parse(term: &str) -> Option<ExerciseFilter> {
+        let mut filter = ExerciseFilter::default();
+        if let Some(category) = Category::parse(term) {
+            filter.category = Some(category);
+        } else if let Some(equipment) = Equipment::parse(term) {
+            filter.equipment = Some(equipment);
+        } else if term.eq_ignore_ascii_case("unilateral") {
+            filter.unilateral = Some(true);
+        } else if let Some(muscle) = MUSCLE_GROUPS.iter().find(|m| m.eq_ignore_ascii_case(term)) {
+            filter.muscle = Some(muscle.to_string());
+        } else {
+            return None;
+        }
+        Some(filter)
+    }
+
+    // SQL condition on an exercises table aliased as "e", taking four parameters starting at $first_param.
+    pub fn sql(first_param: usize) -> String {
+        format!("(${0}::TEXT IS NULL OR e.category=${0}) AND (${1}::TEXT IS NULL OR e.equipment=${1}) AND (${2}::TEXT IS NULL OR EXISTS (SELECT 1 FROM exercise_muscles m WHERE m.exercise_id=e.id AND lower(m.muscle)=lower(${2}))) AND (${3}::BOOLEAN IS NULL OR e.unilateral=${3})", first_param, first_param+1, first_param+2, first_param+3)
+    }
+
+    pub fn params(&self) -> (Option<&'static str>, Option<&'static str>, Option<&str>, Option<bool>) {
+        (self.category.map(|c| c.as_str()), self.equipment.map(|e| e.as_str()), self.muscle.as_deref(), self.unilateral)
+    }
+}
+
+// A lift to be logged. The weight is in `unit`, the prescription (if the lift followed the plan) in kg.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct NewLift {
+    pub exercise_id: i32,
+    pub weight: f32,
+    pub unit: Unit,
+    pub reps: f32,
+    pub sets: f32,
+    pub prescribed_weight: Option<f32>,
+    pub prescribed_reps: Option<f32>,
+    pub notes: Option<String>,
+    pub rpe: Option<f32>,
+    // Seconds rested after the lift, when the rest timer ran.
+    pub rest: Option<i32>,
+}
+
+pub struct LastLift {
+    pub weight: f32,
+    pub unit: Unit,
+    pub reps: f32,
+    pub sets: f32,
+    pub date: NaiveDate,
+}
+
+pub struct ExerciseDefaults {
+    pub weight: Option<f32>,
+    pub reps: Option<f32>,
+    pub sets: Option<f32>,
+    pub bodyweight: bool,
+    pub pr_reps: Option<f32>,
+    // Seconds of rest between sets, None for no rest timer.
+    pub rest: Option<i32>,
+}
+
 impl Db {
     pub fn new(host: &str, user: &str, password: &Option<&str>, dbname: &str) -> Result<Db, Error> {
         let mut client = Db::connect(host, user, password, &Some(dbname));
@@ -17,8 +260,11 @@ impl Db {
             }
         }
 
+        let mut client = client?;
+        Db::update_schema(&mut client)?;
+
         Ok(Db {
-            client: client?,
+            client,
         })
     }
 
@@ -44,7 +290,7 @@ impl Db {
         let mut client = Db::connect(host, user, password, &None)?;
         let mut create_db_query = String::from("create database ");
         create_db_query.push_str(dbname);
-        create_db_query.push_str(";");
+        create_db_query.push(';');
         client.execute(create_db_query.as_str(), &[])?;
         client  = Db::connect(host, user, password, &Some(dbname))?;
         
@@ -62,6 +308,84 @@ impl Db {
         Ok(client)
     }
 
+    fn update_schema(client: &mut Client) -> Result<(), Error> {
+        let has_defaults = Db::has_column(client, "exercises", "default_reps")?;
+        let has_attributes = Db::has_column(client, "exercises", "category")?;
+
+        client.batch_execute("
+            ALTER TABLE exercises ADD COLUMN IF NOT EXISTS archived BOOLEAN NOT NULL DEFAULT FALSE;
+            CREATE TABLE IF NOT EXISTS exercise_aliases (id SERIAL, exercise_id INT NOT NULL, alias TEXT NOT NULL UNIQUE, PRIMARY KEY (id));
+            ALTER TABLE exercises ADD COLUMN IF NOT EXISTS default_weight REAL;
+            ALTER TABLE exercises ADD COLUMN IF NOT EXISTS default_reps REAL DEFAULT 5;
+            ALTER TABLE exercises ADD COLUMN IF NOT EXISTS default_sets REAL DEFAULT 1;
+            ALTER TABLE exercises ADD COLUMN IF NOT EXISTS bodyweight BOOLEAN NOT NULL DEFAULT FALSE;
+            ALTER TABLE exercises ADD COLUMN IF NOT EXISTS pr_reps REAL DEFAULT 5;
+            ALTER TABLE exercises ADD COLUMN IF NOT EXISTS category TEXT;
+            ALTER TABLE exercises ADD COLUMN IF NOT EXISTS equipment TEXT;
+            ALTER TABLE exercises ADD COLUMN IF NOT EXISTS unilateral BOOLEAN NOT NULL DEFAULT FALSE;
+            CREATE TABLE IF NOT EXISTS exercise_muscles (id SERIAL, exercise_id INT NOT NULL, muscle TEXT NOT NULL, is_primary BOOLEAN NOT NULL, PRIMARY KEY (id), UNIQUE (exercise_id, muscle));
+            CREATE TABLE IF NOT EXISTS bodyweights (id SERIAL, date DATE NOT NULL UNIQUE, weight REAL NOT NULL, PRIMARY KEY (id));
+            ALTER TABLE lifts ADD COLUMN IF NOT EXISTS unit TEXT NOT NULL DEFAULT 'kg';
+            ALTER TABLE lifts ADD COLUMN IF NOT EXISTS prescribed_weight REAL;
+            ALTER TABLE lifts ADD COLUMN IF NOT EXISTS prescribed_reps REAL;
+            ALTER TABLE lifts ADD COLUMN IF NOT EXISTS notes TEXT;
+            ALTER TABLE lifts ADD COLUMN IF NOT EXISTS rpe REAL;
+            ALTER TABLE exercises ADD COLUMN IF NOT EXISTS rest_seconds INT;
+            ALTER TABLE lifts ADD COLUMN IF NOT EXISTS rest_seconds INT;
+            ALTER TABLE sessions ADD COLUMN IF NOT EXISTS import_key TEXT UNIQUE;
+            CREATE TABLE IF NOT EXISTS blocks (id SERIAL, name TEXT NOT NULL, start_date DATE NOT NULL UNIQUE, PRIMARY KEY (id));
+            CREATE TABLE IF NOT EXISTS personal_records (id SERIAL, lift_id INT NOT NULL, kind TEXT NOT NULL, value REAL NOT NULL, previous REAL NOT NULL, PRIMARY KEY (id));
+        ")?;
+
+        if !has_defaults {
+            // Defaults that used to be hardcoded by exercise id.
+            client.batch_execute("
+                UPDATE exercises SET default_weight=0, default_reps=NULL, bodyweight=TRUE, pr_reps=NULL WHERE name='Chinups';
+                UPDATE exercises SET default_reps=1, pr_reps=1 WHERE name IN ('Clean', 'Snatch');
+            ")?;
+        }
+
+        if !has_attributes {
+            client.batch_execute("
+                UPDATE exercises e SET category=v.category, equipment=v.equipment FROM (VALUES
+                    ('Squat', 'squat', 'barbell'), ('Bench', 'push', 'barbell'), ('Deadlift', 'hinge', 'barbell'),
+                    ('Press', 'push', 'barbell'), ('Chinups', 'pull', 'bodyweight'), ('Clean', 'olympic', 'barbell'),
+                    ('Lat pulldowns', 'pull', 'machine'), ('Front squat', 'squat', 'barbell'), ('Rows', 'pull', 'barbell'),
+                    ('Snatch', 'olympic', 'barbell')
+                ) AS v(name, category, equipment) WHERE e.name=v.name;
+                INSERT INTO exercise_muscles (exercise_id, muscle, is_primary) SELECT e.id, v.muscle, v.is_primary FROM exercises e JOIN (VALUES
+                    ('Squat', 'Quads', TRUE), ('Squat', 'Glutes', TRUE), ('Squat', 'Hamstrings', FALSE), ('Squat', 'Lower back', FALSE),
+                    ('Bench', 'Chest', TRUE), ('Bench', 'Triceps', FALSE), ('Bench', 'Shoulders', FALSE),
+                    ('Deadlift', 'Hamstrings', TRUE), ('Deadlift', 'Glutes', TRUE), ('Deadlift', 'Lower back', TRUE), ('Deadlift', 'Traps', FALSE), ('Deadlift', 'Forearms', FALSE),
+                    ('Press', 'Shoulders', TRUE), ('Press', 'Triceps', FALSE),
+                    ('Chinups', 'Lats', TRUE), ('Chinups', 'Biceps', FALSE),
+                    ('Clean', 'Quads', TRUE), ('Clean', 'Glutes', TRUE), ('Clean', 'Traps', FALSE), ('Clean', 'Hamstrings', FALSE),
+                    ('Lat pulldowns', 'Lats', TRUE), ('Lat pulldowns', 'Biceps', FALSE),
+                    ('Front squat', 'Quads', TRUE), ('Front squat', 'Glutes', FALSE), ('Front squat', 'Core', FALSE),
+                    ('Rows', 'Upper back', TRUE), ('Rows', 'Lats', TRUE), ('Rows', 'Biceps', FALSE),
+                    ('Snatch', 'Quads', TRUE), ('Snatch', 'Glutes', TRUE), ('Snatch', 'Shoulders', FALSE), ('Snatch', 'Traps', FALSE)
+                ) AS v(name, muscle, is_primary) ON e.name=v.name;
+            ")?;
+        }
+        // Total load adds the lifter's bodyweight on that date to bodyweight exercises, so a negative weight is assistance.
+        client.batch_execute("
+            DROP VIEW IF EXISTS lift_loads CASCADE;
+            CREATE VIEW lift_loads AS SELECT *, (CASE WHEN reps < 1 THEN 0 WHEN reps = 1 THEN load ELSE load * (1 + reps / 30) END)::REAL AS e1rm FROM (
+                SELECT l.id, l.exercise_id, l.session_id, s.date, l.weight, l.reps, l.sets,
+                    l.weight + CASE WHEN e.bodyweight THEN COALESCE((SELECT b.weight FROM bodyweights b WHERE b.date <= s.date ORDER BY b.date DESC LIMIT 1), 0) ELSE 0 END AS load
+                FROM lifts l JOIN sessions s ON s.id=l.session_id JOIN exercises e ON e.id=l.exercise_id
+            ) loads;
+        ")?;
+        Ok(())
+    }
+
+    fn has_column(client: &mut Client, table: &str, column: &str) -> Result<bool, Error> {
+        match client.query_one("SELECT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name=$1 AND column_name=$2);", &[&table, &column]) {
+            Ok(row) => Ok(row.get(0)),
+            Err(err) => Err(err),
+        }
+    }
+
     pub fn select_current_date(&mut self) -> Result<NaiveDate, Error> {
         match self.client.query_one("SELECT CURRENT_DATE;", &[]) {
             Ok(row) => Ok(row.get(0)),
@@ -83,6 +407,13 @@ impl Db {
         }
     }
 
+    pub fn select_first_session_date(&mut self) -> Result<Option<NaiveDate>, Error> {
+        match self.client.query_one("SELECT MIN(date) FROM sessions;", &[]) {
+            Ok(row) => Ok(row.get(0)),
+            Err(err) => Err(err),
+        }
+    }
+
     pub fn insert_session(&mut self, date: &NaiveDate) -> Result<i32, Error> {
         match self.client.query_one("INSERT INTO sessions (date) VALUES ($1) returning id;", &[&date]) {
             Ok(row) => Ok(row.get(0)),
@@ -103,21 +434,130 @@ impl Db {
     }
 
     pub fn select_exercise_weight_pr(&mut self, exercise_id: i32, reps: f32) -> Result<f32, Error> {
-        match self.client.query_one("SELECT weight FROM lifts WHERE exercise_id=$1 AND reps=$2 ORDER BY weight DESC LIMIT 1;", &[&exercise_id, &reps]) {
+        match self.client.query_one("SELECT load FROM lift_loads WHERE exercise_id=$1 AND reps=$2 ORDER BY load DESC LIMIT 1;", &[&exercise_id, &reps]) {
             Ok(row) => Ok(row.get(0)),
             Err(err) => Err(err),
         }
     }
 
-    pub fn select_exercise_reps_pr(&mut self, exercise_id: i32, weight: f32) -> Result<f32, Error> {
-        match self.client.query_one("SELECT reps FROM lifts WHERE exercise_id=$1 AND weight=$2 ORDER BY reps DESC LIMIT 1;", &[&exercise_id, &weight]) {
+    pub fn select_exercise_e1rm_pr(&mut self, exercise_id: i32) -> Result<Option<(f32, NaiveDate)>, Error> {
+        let row = self.client.query_opt("SELECT e1rm, date FROM lift_loads WHERE exercise_id=$1 AND e1rm > 0 ORDER BY e1rm DESC, date LIMIT 1;", &[&exercise_id])?;
+        Ok(row.map(|row| (row.get(0), row.get(1))))
+    }
+
+    pub fn select_bodyweight(&mut self, date: &NaiveDate) -> Result<Option<f32>, Error> {
+        let row = self.client.query_opt("SELECT weight FROM bodyweights WHERE date <= $1 ORDER BY date DESC LIMIT 1;", &[&date])?;
+        Ok(row.map(|row| row.get(0)))
+    }
+
+    pub fn insert_bodyweight(&mut self, date: &NaiveDate, weight: f32) -> Result<(), Error> {
+        self.client.execute("INSERT INTO bodyweights (date, weight) VALUES ($1, $2) ON CONFLICT (date) DO UPDATE SET weight=EXCLUDED.weight;", &[&date, &weight])?;
+        Ok(())
+    }
+
+    pub fn select_exercise_reps_pr(&mut self, exercise_id: i32, load: f32) -> Result<f32, Error> {
+        match self.client.query_one("SELECT reps FROM lift_loads WHERE exercise_id=$1 AND load=$2 ORDER BY reps DESC LIMIT 1;", &[&exercise_id, &load]) {
             Ok(row) => Ok(row.get(0)),
             Err(err) => Err(err),
         }
     }
 
+    // Whether a session on the date holds exactly these lifts, e.g. when a crash left its draft behind after the commit.
+    pub fn select_session_logged(&mut self, date: &NaiveDate, lifts: &[&NewLift]) -> Result<bool, Error> {
+        let rows = self.client.query("SELECT l.session_id, l.exercise_id, l.weight, l.reps, l.sets FROM lifts l JOIN sessions s ON s.id=l.session_id WHERE s.date=$1 ORDER BY l.session_id, l.id;", &[&date])?;
+        let expected: Vec<(i32, f32, f32, f32)> = lifts.iter().map(|lift| (lift.exercise_id, lift.unit.to_kg(lift.weight), lift.reps, lift.sets)).collect();
+        Ok(rows.chunk_by(|a, b| a.get::<_, i32>(0) == b.get::<_, i32>(0)).any(|session| {
+            session.iter().map(|row| -> (i32, f32, f32, f32) { (row.get(1), row.get(2), row.get(3), row.get(4)) }).eq(expected.iter().copied())
+        }))
+    }
+
+    pub fn select_last_lift(&mut self, exercise_id: i32) -> Result<Option<LastLift>, Error> {
+        let row = self.client.query_opt("SELECT l.weight, l.reps, l.sets, s.date, l.unit FROM lifts l JOIN sessions s ON s.id=l.session_id WHERE l.exercise_id=$1 ORDER BY s.date DESC, l.id DESC LIMIT 1;", &[&exercise_id])?;
+        Ok(row.map(|row| Db::row_to_last_lift(&row)))
+    }
+
+    fn row_to_last_lift(row: &Row) -> LastLift {
+        let unit: String = row.get(4);
+        LastLift {weight: row.get(0), unit: Unit::parse(&unit).unwrap_or(Unit::Kg), reps: row.get(1), sets: row.get(2), date: row.get(3)}
+    }
+
+    pub fn select_exercise_defaults(&mut self, exercise_id: i32) -> Result<ExerciseDefaults, Error> {
+        match self.client.query_one("SELECT default_weight, default_reps, default_sets, bodyweight, pr_reps, rest_seconds FROM exercises WHERE id=$1;", &[&exercise_id]) {
+            Ok(row) => Ok(ExerciseDefaults {weight: row.get(0), reps: row.get(1), sets: row.get(2), bodyweight: row.get(3), pr_reps: row.get(4), rest: row.get(5)}),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub fn update_exercise_defaults(&mut self, exercise_id: i32, defaults: &ExerciseDefaults) -> Result<(), Error> {
+        self.client.execute("UPDATE exercises SET default_weight=$2, default_reps=$3, default_sets=$4, bodyweight=$5, pr_reps=$6, rest_seconds=$7 WHERE id=$1;", &[&exercise_id, &defaults.weight, &defaults.reps, &defaults.sets, &defaults.bodyweight, &defaults.pr_reps, &defaults.rest])?;
+        Ok(())
+    }
+
+    pub fn select_exercise_attributes(&mut self, exercise_id: i32) -> Result<ExerciseAttributes, Error> {
+        let row = self.client.query_one("SELECT category, equipment, unilateral FROM exercises WHERE id=$1;", &[&exercise_id])?;
+        let category: Option<String> = row.get(0);
+        let equipment: Option<String> = row.get(1);
+        let mut attributes = ExerciseAttributes {
+            category: category.and_then(|c| Category::parse(&c)),
+            equipment: equipment.and_then(|e| Equipment::parse(&e)),
+            unilateral: row.get(2),
+            primary_muscles: Vec::new(),
+            secondary_muscles: Vec::new(),
+        };
+        for muscle in self.client.query("SELECT muscle, is_primary FROM exercise_muscles WHERE exercise_id=$1 ORDER BY id;", &[&exercise_id])? {
+            match muscle.get(1) {
+                true => attributes.primary_muscles.push(muscle.get(0)),
+                false => attributes.secondary_muscles.push(muscle.get(0)),
+            }
+        }
+        Ok(attributes)
+    }
+
+    pub fn update_exercise_attributes(&mut self, exercise_id: i32, attributes: &ExerciseAttributes) -> Result<(), Error> {
+        let mut transaction = self.client.transaction()?;
+        transaction.execute("UPDATE exercises SET category=$2, equipment=$3, unilateral=$4 WHERE id=$1;", &[&exercise_id, &attributes.category.map(|c| c.as_str()), &attributes.equipment.map(|e| e.as_str()), &attributes.unilateral])?;
+        transaction.execute("DELETE FROM exercise_muscles WHERE exercise_id=$1;", &[&exercise_id])?;
+        for muscle in &attributes.primary_muscles {
+            transaction.execute("INSERT INTO exercise_muscles (exercise_id, muscle, is_primary) VALUES ($1, $2, TRUE);", &[&exercise_id, muscle])?;
+        }
+        for muscle in &attributes.secondary_muscles {
+            transaction.execute("INSERT INTO exercise_muscles (exercise_id, muscle, is_primary) VALUES ($1, $2, FALSE);", &[&exercise_id, muscle])?;
+        }
+        transaction.commit()
+    }
+
+    pub fn select_exercises_filtered(&mut self, filter: &ExerciseFilter) -> Result<Vec<(i32, String)>, Error> {
+        let (category, equipment, muscle, unilateral) = filter.params();
+        let query = self.client.query(format!("SELECT e.id, e.name FROM exercises e WHERE NOT e.archived AND {} ORDER BY e.id;", ExerciseFilter::sql(1)).as_str(), &[&category, &equipment, &muscle, &unilateral])?;
+        let mut ret: Vec<(i32, String)> = Vec::new();
+        for exercise in query {
+            ret.push((exercise.get(0), exercise.get(1)));
+        }
+        Ok(ret)
+    }
+
+    pub fn select_exercise_tags(&mut self) -> Result<Vec<(i32, String)>, Error> {
+        let query = self.client.query(Db::EXERCISE_TAGS_QUERY, &[])?;
+        let mut ret: Vec<(i32, String)> = Vec::new();
+        for tag in query {
+            ret.push((tag.get(0), tag.get(1)));
+        }
+        Ok(ret)
+    }
+
+    const EXERCISE_TAGS_QUERY: &'static str = "SELECT id, category FROM exercises WHERE category IS NOT NULL UNION ALL SELECT id, equipment FROM exercises WHERE equipment IS NOT NULL UNION ALL SELECT id, 'unilateral' FROM exercises WHERE unilateral UNION ALL SELECT exercise_id, lower(muscle) FROM exercise_muscles;";
+
     pub fn select_exercises(&mut self) -> Result<Vec<(i32, String)>, Error> {
-        let query = self.client.query("SELECT id, name FROM exercises;", &[])?;
+        let query = self.client.query("SELECT id, name FROM exercises WHERE NOT archived ORDER BY id;", &[])?;
+        let mut ret: Vec<(i32, String)> = Vec::new();
+        for exercise in query {
+            ret.push((exercise.get(0), exercise.get(1)));
+        }
+        Ok(ret)
+    }
+
+    pub fn select_archived_exercises(&mut self) -> Result<Vec<(i32, String)>, Error> {
+        let query = self.client.query("SELECT id, name FROM exercises WHERE archived ORDER BY id;", &[])?;
         let mut ret: Vec<(i32, String)> = Vec::new();
         for exercise in query {
             ret.push((exercise.get(0), exercise.get(1)));
@@ -125,7 +565,58 @@ impl Db {
         Ok(ret)
     }
 
-    pub fn transaction_start(&mut self) -> Result<Transaction, Error> {
+    pub fn select_exercise_aliases(&mut self) -> Result<Vec<(i32, String)>, Error> {
+        let query = self.client.query("SELECT a.exercise_id, a.alias FROM exercise_aliases a JOIN exercises e ON e.id=a.exercise_id WHERE NOT e.archived ORDER BY a.alias;", &[])?;
+        let mut ret: Vec<(i32, String)> = Vec::new();
+        for alias in query {
+            ret.push((alias.get(0), alias.get(1)));
+        }
+        Ok(ret)
+    }
+
+    // The name of `except_id` itself doesn't count, so an exercise can be renamed to a different case.
+    pub fn select_exercise_name_taken(&mut self, name: &str, except_id: Option<i32>) -> Result<bool, Error> {
+        match self.client.query_one("SELECT EXISTS (SELECT 1 FROM exercises WHERE lower(name)=lower($1) AND ($2::INT IS NULL OR id<>$2)) OR EXISTS (SELECT 1 FROM exercise_aliases WHERE lower(alias)=lower($1));", &[&name, &except_id]) {
+            Ok(row) => Ok(row.get(0)),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub fn rename_exercise(&mut self, exercise_id: i32, exercise_name: &str) -> Result<(), Error> {
+        self.client.execute("UPDATE exercises SET name=$2 WHERE id=$1;", &[&exercise_id, &exercise_name])?;
+        Ok(())
+    }
+
+    pub fn set_exercise_archived(&mut self, exercise_id: i32, archived: bool) -> Result<(), Error> {
+        self.client.execute("UPDATE exercises SET archived=$2 WHERE id=$1;", &[&exercise_id, &archived])?;
+        Ok(())
+    }
+
+    pub fn insert_exercise_alias(&mut self, exercise_id: i32, alias: &str) -> Result<(), Error> {
+        self.client.execute("INSERT INTO exercise_aliases (exercise_id, alias) VALUES ($1, $2);", &[&exercise_id, &alias])?;
+        Ok(())
+    }
+
+    pub fn delete_exercise_alias(&mut self, alias: &str) -> Result<bool, Error> {
+        let deleted = self.client.execute("DELETE FROM exercise_aliases WHERE lower(alias)=lower($1);", &[&alias])?;
+        Ok(deleted > 0)
+    }
+
+    pub fn merge_exercises(&mut self, from_id: i32, into_id: i32) -> Result<(), Error> {
+        let mut transaction = self.client.transaction()?;
+        let from_name: String = transaction.query_one("SELECT name FROM exercises WHERE id=$1;", &[&from_id])?.get(0);
+        transaction.execute("UPDATE lifts SET exercise_id=$2 WHERE exercise_id=$1;", &[&from_id, &into_id])?;
+        transaction.execute("UPDATE exercise_aliases SET exercise_id=$2 WHERE exercise_id=$1;", &[&from_id, &into_id])?;
+        // The muscles of the exercise merged into are kept.
+        transaction.execute("DELETE FROM exercise_muscles WHERE exercise_id=$1;", &[&from_id])?;
+        transaction.execute("DELETE FROM exercises WHERE id=$1;", &[&from_id])?;
+        transaction.execute("INSERT INTO exercise_aliases (exercise_id, alias) VALUES ($1, $2);", &[&into_id, &from_name])?;
+        // The PRs of both were set against separate histories.
+        Db::transaction_recompute_prs(&mut transaction, &[into_id])?;
+        transaction.commit()
+    }
+
+    pub fn transaction_start(&mut self) -> Result<Transaction<'_>, Error> {
         self.client.transaction()
     }
 
@@ -133,6 +624,17 @@ impl Db {
         transaction.commit()
     }
 
+    pub fn transaction_rollback(transaction: Transaction) -> Result<(), Error> {
+        transaction.rollback()
+    }
+
+    pub fn transaction_select_session_on(transaction: &mut Transaction, date: &NaiveDate) -> Result<Option<i32>, Error> {
+        match transaction.query_opt("SELECT id FROM sessions WHERE date=$1 ORDER BY id DESC LIMIT 1;", &[&date]) {
+            Ok(row) => Ok(row.map(|row| row.get(0))),
+            Err(err) => Err(err),
+        }
+    }
+
     pub fn transaction_insert_session(transaction: &mut Transaction, date: &NaiveDate) -> Result<i32, Error> {
         match transaction.query_one("INSERT INTO sessions (date) VALUES ($1) returning id;", &[&date]) {
             Ok(row) => Ok(row.get(0)),
@@ -148,7 +650,7 @@ impl Db {
     }
 
     pub fn transaction_select_exercises(transaction: &mut Transaction) -> Result<Vec<(i32, String)>, Error> {
-        let query = transaction.query("SELECT id, name FROM exercises;", &[])?;
+        let query = transaction.query("SELECT id, name FROM exercises WHERE NOT archived ORDER BY id;", &[])?;
         let mut ret: Vec<(i32, String)> = Vec::new();
         for exercise in query {
             ret.push((exercise.get(0), exercise.get(1)));
@@ -156,10 +658,56 @@ impl Db {
         Ok(ret)
     }
 
-    pub fn transaction_insert_lift(transaction: &mut Transaction, exercise_id: i32, session_id: i32, weight: f32, reps: f32, sets: f32) -> Result<i32, Error> {
-        match transaction.query_one("INSERT INTO lifts (exercise_id, session_id, weight, reps, sets) VALUES ($1, $2, $3, $4, $5) returning id;", &[&exercise_id, &session_id, &weight, &reps, &sets]) {
-            Ok(row) => Ok(row.get(0)),
+    pub fn transaction_select_bodyweight(transaction: &mut Transaction, date: &NaiveDate) -> Result<Option<f32>, Error> {
+        let row = transaction.query_opt("SELECT weight FROM bodyweights WHERE date <= $1 ORDER BY date DESC LIMIT 1;", &[&date])?;
+        Ok(row.map(|row| row.get(0)))
+    }
+
+    pub fn transaction_insert_bodyweight(transaction: &mut Transaction, date: &NaiveDate, weight: f32) -> Result<(), Error> {
+        transaction.execute("INSERT INTO bodyweights (date, weight) VALUES ($1, $2) ON CONFLICT (date) DO UPDATE SET weight=EXCLUDED.weight;", &[&date, &weight])?;
+        Ok(())
+    }
+
+    pub fn transaction_select_last_lift(transaction: &mut Transaction, exercise_id: i32) -> Result<Option<LastLift>, Error> {
+        let row = transaction.query_opt("SELECT l.weight, l.reps, l.sets, s.date, l.unit FROM lifts l JOIN sessions s ON s.id=l.session_id WHERE l.exercise_id=$1 ORDER BY s.date DESC, l.id DESC LIMIT 1;", &[&exercise_id])?;
+        Ok(row.map(|row| Db::row_to_last_lift(&row)))
+    }
+
+    pub fn transaction_select_exercise_defaults(transaction: &mut Transaction, exercise_id: i32) -> Result<ExerciseDefaults, Error> {
+        match transaction.query_one("SELECT default_weight, default_reps, default_sets, bodyweight, pr_reps, rest_seconds FROM exercises WHERE id=$1;", &[&exercise_id]) {
+            Ok(row) => Ok(ExerciseDefaults {weight: row.get(0), reps: row.get(1), sets: row.get(2), bodyweight: row.get(3), pr_reps: row.get(4), rest: row.get(5)}),
             Err(err) => Err(err),
         }
     }
+
+    pub fn transaction_select_exercise_tags(transaction: &mut Transaction) -> Result<Vec<(i32, String)>, Error> {
+        let query = transaction.query(Db::EXERCISE_TAGS_QUERY, &[])?;
+        let mut ret: Vec<(i32, String)> = Vec::new();
+        for tag in query {
+            ret.push((tag.get(0), tag.get(1)));
+        }
+        Ok(ret)
+    }
+
+    pub fn transaction_select_exercise_aliases(transaction: &mut Transaction) -> Result<Vec<(i32, String)>, Error> {
+        let query = transaction.query("SELECT a.exercise_id, a.alias FROM exercise_aliases a JOIN exercises e ON e.id=a.exercise_id WHERE NOT e.archived ORDER BY a.alias;", &[])?;
+        let mut ret: Vec<(i32, String)> = Vec::new();
+        for alias in query {
+            ret.push((alias.get(0), alias.get(1)));
+        }
+        Ok(ret)
+    }
+
+    // Weights are stored in kg, the unit the lift was entered in is kept alongside.
+    pub fn transaction_insert_lift(transaction: &mut Transaction, session_id: i32, lift: &NewLift) -> Result<i32, Error> {
+        let lift_id = Db::transaction_insert_lift_without_prs(transaction, session_id, lift)?;
+        Db::transaction_detect_prs(transaction, lift_id)?;
+        Ok(lift_id)
+    }
+
+    // For bulk inserts, which recompute the PRs of the exercises afterwards.
+    pub(crate) fn transaction_insert_lift_without_prs(transaction: &mut Transaction, session_id: i32, lift: &NewLift) -> Result<i32, Error> {
+        let lift_id: i32 = transaction.query_one("INSERT INTO lifts (exercise_id, session_id, weight, unit, reps, sets, prescribed_weight, prescribed_reps, notes, rpe, rest_seconds) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11) returning id;", &[&lift.exercise_id, &session_id, &lift.unit.to_kg(lift.weight), &lift.unit.as_str(), &lift.reps, &lift.sets, &lift.prescribed_weight, &lift.prescribed_reps, &lift.notes, &lift.rpe, &lift.rest])?.get(0);
+        Ok(lift_id)
+    }
 }
@@ -1,27 +1,102 @@
+use std::collections::BTreeMap;
 use std::io;
-use std::io::{Read, Write};
+use std::io::{BufWriter, Read, Write};
 use postgres::{Error, Transaction};
 
 use std::fs::File;
 
 use std::env;
+use std::process::ExitCode;
 
-use liftsql::Db;
+use liftsql::{Db, Category, DateOrder, Equipment, ExerciseFilter, LiftEntry, NewLift, PersonalRecord, PrBoard, PrKind, Unit, MUSCLE_GROUPS};
+use liftsql::analysis::{StallReport, DELOAD_PERCENT};
+use liftsql::export::{ExportQuery, ExportRows};
+use liftsql::apps::{App, AppImportOptions};
+use liftsql::backup::Backup;
+use liftsql::import::{ColumnMapping, ImportOptions, ImportReport};
+use liftsql::stats::{GroupStats, Period, StatsQuery};
 
 use chrono::{NaiveDate, Datelike, Duration};
+use serde::{Deserialize, Serialize};
 
-fn main() {
-    let mut tui = Tui::new();
-    tui.run();
+mod chart;
+mod cli;
+mod draft;
+mod prompt;
+mod screen;
+mod timer;
+
+use clap::Parser;
+use cli::Cli;
+use draft::Draft;
+
+fn main() -> ExitCode {
+    let cli = Cli::parse_from(cli::args());
+    match cli.command {
+        Some(command) => cli::run(command),
+        None => {
+            let mut tui = Tui::new();
+            tui.run(cli.line);
+            ExitCode::SUCCESS
+        },
+    }
 }
 
+type PlanExercise = (i32, Weight, Reps, i32);
+type PlanDay<'a> = (&'a str, Vec<PlanExercise>);
+
 struct Tui<'a> {
     db: Db,
-    plan: Vec<(&'a str, Vec<(i32, Weight, Reps, i32)>)>,
+    plan: Vec<PlanDay<'a>>,
+    unit: Unit,
+    stall_sessions: usize,
+    date_order: DateOrder,
 }
 
 struct Config {
     day_id: i32,
+    unit: Unit,
+    stall_sessions: usize,
+    date_order: DateOrder,
+}
+
+enum ImportFormat {
+    // Column mapping and date format of a generic CSV file.
+    Csv(ColumnMapping, Option<String>),
+    App(App),
+}
+
+// A prescription of a plan day as shown by the plan and `liftsql plan show`. Weights are in kg as they are logged,
+// so for bodyweight exercises they are the added weight.
+#[derive(Serialize)]
+struct PlannedLift {
+    exercise_id: i32,
+    exercise_name: String,
+    sets: i32,
+    // None for AMRAPs and exercises without a PR to base the weight on.
+    reps: Option<f32>,
+    weight: Option<f32>,
+    // Percent of the rep max the weight is based on, None for fixed weights.
+    rm_percent: Option<f32>,
+    amrap: bool,
+    pr_attempt: bool,
+    // Only set when the exercise is stalled.
+    stall: Option<StallReport>,
+}
+
+// A lift of a session that isn't saved yet, so it can still be edited, moved or dropped.
+#[derive(Clone, Serialize, Deserialize)]
+struct PendingLift {
+    exercise_name: String,
+    lift: NewLift,
+}
+
+struct Prescription {
+    exercise_id: i32,
+    weight: Option<f32>,
+    reps: Option<f32>,
+    sets: i32,
+    amrap: bool,
 }
 
 enum Weight {
@@ -30,13 +105,13 @@ enum Weight {
 }
 
 enum Reps {
-    AMRAP,
+    Amrap,
     Static(f32),
 }
 
 impl Config {
     fn default() -> Config {
-        Config {day_id: 0,}
+        Config {day_id: 0, unit: Unit::Kg, stall_sessions: 4, date_order: DateOrder::Dmy}
     }
 }
 
@@ -46,11 +121,22 @@ impl Tui<'_> {
             Ok(db_) => db_,
             Err(err) => panic!("{}", err),
         };
-        let plan = vec![("Volume Bench", vec![(1, Weight::RMPer(90.0), Reps::Static(5.0), 5), (2, Weight::RMPer(90.0), Reps::Static(5.0), 5), (3, Weight::RMPer(100.0), Reps::Static(5.0), 1)]), ("Recovery Press", vec![(1, Weight::RMPer(72.0), Reps::Static(5.0), 2), (4, Weight::RMPer(81.0), Reps::Static(5.0), 3), (5, Weight::Static(0.0), Reps::AMRAP, 1)]), ("PR Press", vec![(1, Weight::RMPer(100.0), Reps::Static(5.0), 1), (4, Weight::RMPer(100.0), Reps::Static(5.0), 1), (6, Weight::RMPer(100.0), Reps::Static(3.0), 5)]), ("Volume Press", vec![(1, Weight::RMPer(90.0), Reps::Static(5.0), 5), (4, Weight::RMPer(90.0), Reps::Static(5.0), 5), (3, Weight::RMPer(100.0), Reps::Static(5.0), 1)]), ("Recovery Bench", vec![(1, Weight::RMPer(72.0), Reps::Static(5.0), 2), (2, Weight::RMPer(81.0), Reps::Static(5.0), 3), (5, Weight::Static(0.0), Reps::AMRAP, 1)]), ("PR Bench", vec![(1, Weight::RMPer(100.0), Reps::Static(5.0), 1), (2, Weight::RMPer(100.0), Reps::Static(5.0), 1), (6, Weight::RMPer(100.0), Reps::Static(3.0), 5)])];
-        Tui {db, plan}
+        Tui::with_db(db)
     }
 
-    const CONFIG_DIR: &str = ".config/.liftsql";
+    fn with_db(db: Db) -> Tui<'static> {
+        let plan = vec![("Volume Bench", vec![(1, Weight::RMPer(90.0), Reps::Static(5.0), 5), (2, Weight::RMPer(90.0), Reps::Static(5.0), 5), (3, Weight::RMPer(100.0), Reps::Static(5.0), 1)]), ("Recovery Press", vec![(1, Weight::RMPer(72.0), Reps::Static(5.0), 2), (4, Weight::RMPer(81.0), Reps::Static(5.0), 3), (5, Weight::Static(0.0), Reps::Amrap, 1)]), ("PR Press", vec![(1, Weight::RMPer(100.0), Reps::Static(5.0), 1), (4, Weight::RMPer(100.0), Reps::Static(5.0), 1), (6, Weight::RMPer(100.0), Reps::Static(3.0), 5)]), ("Volume Press", vec![(1, Weight::RMPer(90.0), Reps::Static(5.0), 5), (4, Weight::RMPer(90.0), Reps::Static(5.0), 5), (3, Weight::RMPer(100.0), Reps::Static(5.0), 1)]), ("Recovery Bench", vec![(1, Weight::RMPer(72.0), Reps::Static(5.0), 2), (2, Weight::RMPer(81.0), Reps::Static(5.0), 3), (5, Weight::Static(0.0), Reps::Amrap, 1)]), ("PR Bench", vec![(1, Weight::RMPer(100.0), Reps::Static(5.0), 1), (2, Weight::RMPer(100.0), Reps::Static(5.0), 1), (6, Weight::RMPer(100.0), Reps::Static(3.0), 5)])];
+        let config = Config::default();
+        let mut tui = Tui {db, plan, unit: config.unit, stall_sessions: config.stall_sessions, date_order: config.date_order};
+        if let Ok(config) = tui.read_config() {
+            tui.unit = config.unit;
+            tui.stall_sessions = config.stall_sessions;
+            tui.date_order = config.date_order;
+        }
+        tui
+    }
+
+    const CONFIG_DIR: &'static str = ".config/.liftsql";
 
     fn read_config(&self) -> Result<Config, io::Error> {
         let mut config_path = match env::home_dir() {
@@ -62,7 +148,24 @@ impl Tui<'_> {
         let mut file = File::open(config_path.as_path())?;
         let mut ret = String::new();
         file.read_to_string(&mut ret)?;
-        Ok(Config {day_id: ret.parse().unwrap(),})
+        Tui::parse_config(&ret)
+    }
+
+    fn parse_config(text: &str) -> Result<Config, io::Error> {
+        let mut config = Config::default();
+        for line in text.lines() {
+            let invalid = || io::Error::new(io::ErrorKind::InvalidData, format!("invalid config line '{}'", line));
+            match line.split_once('=') {
+                Some(("day_id", value)) => config.day_id = value.trim().parse().map_err(|_| invalid())?,
+                Some(("unit", value)) => config.unit = Unit::parse(value.trim()).ok_or_else(invalid)?,
+                Some(("stall_sessions", value)) => config.stall_sessions = value.trim().parse().map_err(|_| invalid())?,
+                Some(("date_order", value)) => config.date_order = DateOrder::parse(value.trim()).ok_or_else(invalid)?,
+                Some(_) => return Err(invalid()),
+                // Older configs only held the plan day.
+                None => config.day_id = line.trim().parse().map_err(|_| invalid())?,
+            }
+        }
+        Ok(config)
     }
 
     fn write_config(&self, config: &Config) -> Result<(), io::Error> {
@@ -73,61 +176,115 @@ impl Tui<'_> {
         config_path.push(Tui::CONFIG_DIR);
 
         let mut file = File::create(config_path.as_path())?;
-        //file.write_all(data.as_bytes())?;
-        file.write_all(config.day_id.to_string().as_bytes())?;
+        file.write_all(Tui::config_string(config).as_bytes())?;
         Ok(())
     }
 
-    fn run(&mut self) {
-        self.dialogue_menu();
+    fn config_string(config: &Config) -> String {
+        format!("day_id={}\nunit={}\nstall_sessions={}\ndate_order={}\n", config.day_id, config.unit.as_str(), config.stall_sessions, config.date_order.as_str())
+    }
+
+    fn run(&mut self, line_mode: bool) {
+        if line_mode || !screen::is_supported() {
+            self.dialogue_menu();
+        } else {
+            match screen::run(self) {
+                Ok(true) => self.dialogue_menu(),
+                Ok(false) => {},
+                Err(err) => println!("ERROR RUNNING FULL-SCREEN UI: {}", err),
+            }
+        }
     }
 
     fn get_user_input(prompt: &str) -> String {
-        print!("{}",prompt);
-        io::stdout().flush().expect("failed to flush buffer.");
-        let mut buffer: String = String::new();
-        io::stdin().read_line(&mut buffer).expect("Failed to read line.");
-        String::from(buffer.trim())
+        prompt::read_line(prompt)
+    }
+
+    fn get_user_input_exercise(prompt: &str) -> String {
+        prompt::read_line_exercise(prompt)
+    }
+
+    fn update_completions(&mut self) -> Result<(), Error> {
+        let exercises = self.db.select_exercises()?;
+        let aliases = self.db.select_exercise_aliases()?;
+        prompt::set_completions(exercises.into_iter().chain(aliases).map(|exercise| exercise.1).collect());
+        Ok(())
     }
 
     fn get_user_input_float(prompt: &str, default: Option<f32>) -> Option<f32> {
         loop {
-            let inp = Tui::get_user_input(&prompt);
+            let inp = Tui::get_user_input(prompt);
             if inp == "q" || inp == "c" {
                 return None;
             }
-            if inp == "" {
+            if inp.is_empty() {
                 if let Some(def) = default {
                     return Some(def);
                 }
             }
             match inp.parse::<f32>() {
-                Ok(i) => return Some(i),
-                Err(_) => {println!("Invalid input."); continue;},
+                Ok(i) if i.is_finite() => return Some(i),
+                _ => {println!("Invalid input."); continue;},
+            }
+        }
+    }
+
+    // Returns None when cancelled and Some(None) when the input was empty without a default.
+    fn get_user_input_weight(prompt: &str, default: Option<f32>, unit: Unit) -> Option<Option<(f32, Unit)>> {
+        loop {
+            let inp = Tui::get_user_input(prompt);
+            if inp == "q" || inp == "c" {
+                return None;
+            }
+            if inp.is_empty() {
+                return Some(default.map(|def| (def, unit)));
+            }
+            match Unit::parse_weight(&inp) {
+                Some((w, inp_unit)) => return Some(Some((w, inp_unit.unwrap_or(unit)))),
+                None => {println!("Invalid input."); continue;},
+            }
+        }
+    }
+
+    fn get_user_input_optional_float(label: &str, current: Option<f32>) -> Option<Option<f32>> {
+        let current_str = match current {
+            Some(f) => f.to_string(),
+            None => String::from("-"),
+        };
+        loop {
+            let inp = Tui::get_user_input(format!("{} ({}): ", label, current_str).as_str());
+            match inp.as_str() {
+                "q" | "c" => return None,
+                "" => return Some(current),
+                "-" => return Some(None),
+                _ => match inp.parse::<f32>() {
+                    Ok(f) => return Some(Some(f)),
+                    Err(_) => println!("Invalid input."),
+                },
             }
         }
     }
 
     fn dialogue_menu(&mut self) {
-        if let Err(_) = self.print_last_session_ago() {
+        if self.print_last_session_ago().is_err() {
             println!("COULDN'T GET LAST SESSION INFO");
         }
+        if let Some(draft) = self.dialogue_draft(true) {
+            self.new_session(Some(draft));
+        }
 
-        println!("n) New session\np) Show plan\ng) Get pr\na) Add exercise\nq) Quit");
+        println!("n) New session\np) Show plan\ng) Get pr\nr) PR board\nt) PR timeline\nx) Stats\na) Add exercise\ne) Edit exercises\nb) Log bodyweight\no) Export CSV\ni) Import CSV\nd) Backup to JSON\nu) Restore JSON backup\ns) Settings\nq) Quit");
         loop {
             println!("=====");
+            // Exercises may have been added or renamed since the last round.
+            if let Err(err) = self.update_completions() {
+                println!("ERROR LOADING EXERCISE NAMES: {}", err);
+            }
             let inp = Tui::get_user_input("$ ");
             match inp.as_str() {
                 "n" => {
-                    match self.dialogue_new_session() {
-                        Ok(done) => {
-                            match done {
-                                true => println!("+ Session logged."),
-                                false => println!("+ Session creation cancelled."),
-                            };
-                        },
-                        Err(err) => println!("+ ERROR CREATING NEW SESSION: {}", err),
-                    }
+                    let draft = self.dialogue_draft(false);
+                    self.new_session(draft);
                 },
                 "p" => {
                     self.dialogue_plan();
@@ -142,6 +299,21 @@ impl Tui<'_> {
                         Err(_) => println!("ERROR GETTING PR"),
                     };
                 },
+                "r" => {
+                    if let Err(err) = self.dialogue_pr_board() {
+                        println!("ERROR GETTING PR BOARD: {}", err);
+                    }
+                },
+                "t" => {
+                    if let Err(err) = self.dialogue_pr_timeline() {
+                        println!("ERROR GETTING PR TIMELINE: {}", err);
+                    }
+                },
+                "x" => {
+                    if let Err(err) = self.dialogue_stats() {
+                        println!("ERROR GETTING STATS: {}", err);
+                    }
+                },
                 "a" => {
                     match self.dialogue_add_exercise() {
                         Ok(success) => {
@@ -153,6 +325,51 @@ impl Tui<'_> {
                         Err(_) => println!("ERROR ADDING EXERCISE"),
                     };
                 },
+                "e" => {
+                    if let Err(err) = self.dialogue_edit_exercises() {
+                        println!("ERROR EDITING EXERCISES: {}", err);
+                    }
+                },
+                "b" => {
+                    match self.dialogue_bodyweight() {
+                        Ok(true) => println!("Bodyweight logged."),
+                        Ok(false) => println!("Bodyweight logging cancelled."),
+                        Err(err) => println!("ERROR LOGGING BODYWEIGHT: {}", err),
+                    }
+                },
+                "o" => {
+                    match self.dialogue_export() {
+                        Ok(true) => {},
+                        Ok(false) => println!("Export cancelled."),
+                        Err(err) => println!("ERROR EXPORTING: {}", err),
+                    }
+                },
+                "i" => {
+                    match self.dialogue_import() {
+                        Ok(true) => {},
+                        Ok(false) => println!("Import cancelled."),
+                        Err(err) => println!("ERROR IMPORTING: {}", err),
+                    }
+                },
+                "d" => {
+                    match self.dialogue_backup() {
+                        Ok(true) => {},
+                        Ok(false) => println!("Backup cancelled."),
+                        Err(err) => println!("ERROR WRITING BACKUP: {}", err),
+                    }
+                },
+                "u" => {
+                    match self.dialogue_restore() {
+                        Ok(true) => {},
+                        Ok(false) => println!("Restore cancelled."),
+                        Err(err) => println!("ERROR RESTORING BACKUP: {}", err),
+                    }
+                },
+                "s" => {
+                    if let Err(err) = self.dialogue_settings() {
+                        println!("ERROR SAVING CONFIG: {}", err);
+                    }
+                },
                 "q" => return,
                 "c" => return,
                 &_ => {
@@ -187,71 +404,143 @@ impl Tui<'_> {
         }
     }
 
-    fn print_day(&mut self, day_id: i32) -> Result<bool, Error> {
+    // Prescriptions of a plan day with the weights rounded to plates of the display unit. None if there's no such day.
+    fn select_plan_day(&mut self, day_id: i32) -> Result<Option<(String, Vec<PlannedLift>)>, Error> {
         let day = match self.plan.get(day_id as usize) {
             Some(d) => d,
-            None => {println!("ERROR GETTING plan day id."); return Ok(false);}
+            None => return Ok(None),
         };
-        println!("{}", day.0);
+        let mut lifts: Vec<PlannedLift> = Vec::new();
         for exercise in &day.1 {
-            let name = self.db.select_exercise_name(exercise.0)?; 
-            let weight = match exercise.1 {
-                Weight::RMPer(percent) => {
-                    match exercise.2 {
-                        Reps::AMRAP => {println!("ERROR CALCULATING AMRAP PR WEIGHT. TODO."); return Ok(false);},
-                        Reps::Static(r) => {
-                            match self.db.select_exercise_weight_pr(exercise.0, r) {
-                                Ok(w) => Some(w*percent/100.0),
-                                Err(_) => None,
-                            }
-                        },
-                    }
-                },
-                Weight::Static(w) => Some(w),
-            };
-            let reps = match exercise.2 {
-                Reps::AMRAP => {
-                    match weight {
-                        Some(w) => {
-                            match self.db.select_exercise_reps_pr(exercise.0, w) {
-                                Ok(r) => Some(r),
-                                Err(_) => None,
-                            }
-                        },
-                        None => None,
-                    }
+            let (weight, reps) = Tui::prescribe(&mut self.db, exercise);
+            let stall = self.db.select_stall_report(exercise.0, self.stall_sessions)?.filter(|report| report.is_stalled());
+            lifts.push(PlannedLift {
+                exercise_id: exercise.0,
+                exercise_name: self.db.select_exercise_name(exercise.0)?,
+                sets: exercise.3,
+                reps,
+                weight: weight.map(|w| Tui::round_prescription(w, self.unit)),
+                rm_percent: match exercise.1 {
+                    Weight::RMPer(percent) => Some(percent),
+                    Weight::Static(_) => None,
                 },
-                Reps::Static(r) => Some(r),
-            };
+                amrap: matches!(exercise.2, Reps::Amrap),
+                pr_attempt: matches!(exercise.1, Weight::RMPer(w) if w == 100.0),
+                stall,
+            });
+        }
+        Ok(Some((day.0.to_string(), lifts)))
+    }
+
+    fn print_day(&mut self, day_id: i32) -> Result<bool, Error> {
+        let (name, lifts) = match self.select_plan_day(day_id)? {
+            Some(day) => day,
+            None => {println!("ERROR GETTING plan day id."); return Ok(false);}
+        };
+        println!("{}", name);
+        for lift in &lifts {
+            if lift.amrap && lift.rm_percent.is_some() {
+                println!("ERROR CALCULATING AMRAP PR WEIGHT. TODO.");
+                return Ok(false);
+            }
 
-            let str_reps = match reps {
+            let str_reps = match lift.reps {
                 Some(r) => r.to_string(),
                 None => String::from("?"),
             };
-            let str_weight = match weight {
-                Some(w) => w.to_string(),
-                None => String::from("?"),
+            let str_weight = match lift.weight {
+                Some(w) => self.unit.format(w),
+                None => format!("?{}", self.unit.as_str()),
             };
 
             let mut pr_reps = String::new();
             let mut pr_weight = String::new();
             let mut pr_sign = String::new();
-            if let Reps::AMRAP = exercise.2 {
+            if lift.amrap {
                 pr_reps = String::from(">");
                 pr_sign.push_str(" *AMRAP*");
             }
-            if let Weight::RMPer(w) = exercise.1 {
-                if w == 100.0 {
-                    pr_weight = String::from(">");
-                    pr_sign.push_str(" *PR*");
-                }
+            if lift.pr_attempt {
+                pr_weight = String::from(">");
+                pr_sign.push_str(" *PR*");
+            }
+            println!("{} {}x{}{} {}{}{}", lift.exercise_name, lift.sets, pr_reps, str_reps, pr_weight, str_weight, pr_sign);
+
+            if let Some(report) = &lift.stall {
+                println!("  ! Stalled: no e1RM PR in {} sessions (best {} on {}), {} of the last {} sessions missed the plan.", report.sessions_since_pr, self.unit.format(report.best_e1rm), report.best_e1rm_date.format("%d.%m."), report.failed_sessions, report.sessions);
+                let deload = match (lift.rm_percent, lift.weight) {
+                    (Some(percent), _) => format!("{}% instead of {}%", percent * DELOAD_PERCENT / 100.0, percent),
+                    (None, Some(w)) => self.unit.format(Tui::round_prescription(w * DELOAD_PERCENT / 100.0, self.unit)),
+                    (None, None) => format!("{}% of the usual weight", DELOAD_PERCENT),
+                };
+                println!("  ! Suggested deload: {}x{} at {}", lift.sets, str_reps, deload);
             }
-            println!("{} {}x{}{} {}{}kg{}", name, exercise.3, pr_reps, str_reps, pr_weight, str_weight, pr_sign);
         }
         Ok(true)
     }
 
+    fn prescribe(db: &mut Db, exercise: &PlanExercise) -> (Option<f32>, Option<f32>) {
+        let weight = match exercise.1 {
+            Weight::RMPer(percent) => {
+                match exercise.2 {
+                    Reps::Amrap => None,
+                    Reps::Static(r) => {
+                        match db.select_exercise_weight_pr(exercise.0, r) {
+                            // PRs are total load, so take the current bodyweight back off for bodyweight exercises.
+                            Ok(w) => Some(w*percent/100.0 - Tui::get_current_bodyweight(db, exercise.0)),
+                            Err(_) => None,
+                        }
+                    },
+                }
+            },
+            Weight::Static(w) => Some(w),
+        };
+        let reps = match exercise.2 {
+            Reps::Amrap => {
+                match weight {
+                    Some(w) => {
+                        // Reps PRs are by total load too.
+                        let load = w + Tui::get_current_bodyweight(db, exercise.0);
+                        db.select_exercise_reps_pr(exercise.0, load).ok()
+                    },
+                    None => None,
+                }
+            },
+            Reps::Static(r) => Some(r),
+        };
+        (weight, reps)
+    }
+
+    // Prescribed weights are rounded to what can be loaded with the plates of the display unit.
+    fn round_prescription(kg: f32, unit: Unit) -> f32 {
+        unit.to_kg(unit.round_to_plates(unit.from_kg(kg)))
+    }
+
+    fn get_current_bodyweight(db: &mut Db, exercise_id: i32) -> f32 {
+        match db.select_exercise_defaults(exercise_id) {
+            Ok(defaults) if defaults.bodyweight => {},
+            _ => return 0.0,
+        }
+        match db.select_current_date().and_then(|date| db.select_bodyweight(&date)) {
+            Ok(Some(bodyweight)) => bodyweight,
+            _ => 0.0,
+        }
+    }
 
+    fn get_day_prescriptions(&mut self) -> Vec<Prescription> {
+        let day_id = match self.read_config() {
+            Ok(conf) => conf.day_id,
+            Err(_) => Config::default().day_id,
+        };
+        let mut ret: Vec<Prescription> = Vec::new();
+        if let Some(day) = self.plan.get(day_id as usize) {
+            for exercise in &day.1 {
+                let (weight, reps) = Tui::prescribe(&mut self.db, exercise);
+                ret.push(Prescription {exercise_id: exercise.0, weight, reps, sets: exercise.3, amrap: matches!(exercise.2, Reps::Amrap)});
+            }
+        }
+        ret
+    }
 
     fn print_last_session_ago(&mut self) -> Result<(), Error> {
         match self.db.select_last_session_id() {
@@ -271,7 +560,7 @@ impl Tui<'_> {
         let mut ret = ago_num.abs().to_string();
         ret.push_str(" day");
         if ago_num != 1 && ago_num != -1 {
-            ret.push_str("s");
+            ret.push('s');
         }
         if ago_num < 0 {
             ret.push_str(" AHEAD");
@@ -281,108 +570,342 @@ impl Tui<'_> {
         ret
     }
 
-    fn dialogue_new_session(&mut self) -> Result<bool, Error> {
-        let mut session_date = self.db.select_current_date()?;
+    fn new_session(&mut self, draft: Option<Draft>) {
+        match self.dialogue_new_session(draft) {
+            Ok(done) => {
+                match done {
+                    true => println!("+ Session logged."),
+                    false => println!("+ Session creation cancelled."),
+                };
+            },
+            Err(err) => println!("+ ERROR CREATING NEW SESSION: {}", err),
+        }
+    }
+
+    // A session left unsaved by an earlier run, if it should be resumed. Later keeps it for the next start.
+    fn dialogue_draft(&mut self, offer_later: bool) -> Option<Draft> {
+        let draft = match Draft::read() {
+            Ok(draft) => draft?,
+            Err(err) => {
+                println!("ERROR READING SESSION DRAFT: {}", err);
+                return None;
+            },
+        };
+        let logged = draft.logged(&mut self.db).unwrap_or_else(|err| {
+            println!("+ !!! COULDN'T CHECK WHETHER THE DRAFT IS ALREADY LOGGED: {}", err);
+            false
+        });
+        // An already logged draft is discarded by default, resuming it would log the session twice.
+        let prompt = match logged {
+            true => {
+                println!("+ !!! THE UNSAVED SESSION OF {} IS ALREADY LOGGED WITH THE SAME {} LIFTS.", draft.date.format("%d.%m."), draft.lifts.len());
+                format!("+ Log it a second time? (resume/[DISCARD]{}) ", if offer_later { "/later" } else { "" })
+            },
+            false => format!("+ Resume the unsaved session of {} with {} lifts? ([YES]/discard{}) ", draft.date.format("%d.%m."), draft.lifts.len(), if offer_later { "/later" } else { "" }),
+        };
         loop {
-            let inp = Tui::get_user_input("+ Session date: ");
-            match inp.parse::<i32>() {
-                Ok(i) => {
-                    session_date = session_date - Duration::days(i.into());
-                    break;
-                },
-                Err(_) => {
-                    if inp == "" {
-                        break;
-                    } else if inp == "q" || inp == "c" {
-                        return Ok(false);
-                    }
-                    if let Some(date_literal) = Tui::parse_date(&inp, session_date.year()) {
-                        session_date = date_literal;
-                        break;
+            match Tui::get_user_input(&prompt).as_str() {
+                "" if !logged => return Some(draft),
+                "y" | "yes" | "r" | "resume" => return Some(draft),
+                "" | "d" | "discard" | "n" | "no" => {
+                    if let Err(err) = Draft::remove() {
+                        println!("+ !!! COULDN'T REMOVE DRAFT: {}", err);
                     }
-
-                    println!("+ !!! Invalid input.");
+                    return None;
                 },
+                "l" | "later" if offer_later => return None,
+                _ => println!("+ !!! Invalid input."),
             }
         }
-        
+    }
+
+    fn dialogue_new_session(&mut self, draft: Option<Draft>) -> Result<bool, Error> {
+        let mut draft = match draft {
+            Some(draft) => draft,
+            None => match self.dialogue_new_draft()? {
+                Some(draft) => draft,
+                None => return Ok(false),
+            },
+        };
+
+        let prescriptions = self.get_day_prescriptions();
         let mut transaction = self.db.transaction_start()?;
-        let new_session_id = Db::transaction_insert_session(&mut transaction, &session_date)?;
+        let new_session_id = Db::transaction_insert_session(&mut transaction, &draft.date)?;
+        if let Some(bodyweight) = draft.bodyweight {
+            Db::transaction_insert_bodyweight(&mut transaction, &draft.date, bodyweight)?;
+        }
         let new_session_date = Db::transaction_select_session_date(&mut transaction, new_session_id)?.format("%d.%m.");
         println!("+ ... Creating session on {}", new_session_date);
-        if Tui::add_lifts(&mut transaction, new_session_id)? == 0 {
-            return Ok(false);
-        }
-        if Tui::get_user_input("+ Log session? ([YES]/cancel)") != "" {
+        if !Tui::add_lifts(&mut transaction, new_session_id, &prescriptions, self.unit, &mut draft)? {
+            Tui::remove_draft();
             return Ok(false);
         }
         Db::transaction_commit(transaction)?;
+        Tui::remove_draft();
         Ok(true)
     }
 
-    fn parse_date(inp: &String, base_year: i32) -> Option<NaiveDate> {
-        let inp_split: Vec<&str> = inp.split(".").collect();
-        if inp_split.len() < 2 || inp_split.len() > 3 {
-            return None;
+    // Asks for the date and bodyweight of a new session. None if cancelled.
+    fn dialogue_new_draft(&mut self) -> Result<Option<Draft>, Error> {
+        let today = self.db.select_current_date()?;
+        let session_date = match self.dialogue_session_date(&today) {
+            Some(date) => date,
+            None => return Ok(None),
+        };
+
+        let last_bodyweight = self.db.select_bodyweight(&session_date)?;
+        let bodyweight_def_str = match last_bodyweight {
+            Some(w) => format!(" ({})", self.unit.format(w)),
+            None => String::new(),
+        };
+        let bodyweight = match Tui::get_user_input_weight(format!("+ Bodyweight{}: ", bodyweight_def_str).as_str(), None, self.unit) {
+            Some(Some((w, unit))) if w > 0.0 => Some(unit.to_kg(w)),
+            Some(_) => None,
+            None => return Ok(None),
+        };
+        Ok(Some(Draft {date: session_date, bodyweight, lifts: Vec::new()}))
+    }
+
+    fn sync_draft(draft: &Draft) {
+        if let Err(err) = draft.sync() {
+            println!("+ !!! COULDN'T SAVE DRAFT: {}", err);
+        }
+    }
+
+    fn remove_draft() {
+        if let Err(err) = Draft::remove() {
+            println!("+ !!! COULDN'T REMOVE DRAFT: {}", err);
         }
-        let day = inp_split.get(0)?.parse::<u32>().ok()?;
-        let month = inp_split.get(1)?.parse::<u32>().ok()?;
-        let mut year = base_year;
-        if inp_split.len() == 3 {
-            year = inp_split.get(2)?.parse::<i32>().ok()?;
+    }
+
+    // Empty for today. Any other date is shown resolved to be confirmed, one in the future needs an explicit yes.
+    fn dialogue_session_date(&self, today: &NaiveDate) -> Option<NaiveDate> {
+        loop {
+            let inp = Tui::get_user_input("+ Session date: ");
+            let date = match inp.as_str() {
+                "" => return Some(*today),
+                "q" | "c" => return None,
+                _ => match liftsql::parse_date(&inp, today, self.date_order) {
+                    Some(date) => date,
+                    None => {
+                        println!("+ !!! Invalid date. Try yesterday, mon, 3, -3d, -1w, {} or {}.", today.format("%Y-%m-%d"), self.date_order.format(today));
+                        continue;
+                    },
+                },
+            };
+            let date_str = format!("{} {}", date.weekday(), self.date_order.format(&date));
+            let confirmed = match (*today - date).num_days() {
+                days if days < 0 => Tui::get_user_input(format!("+ !!! {} is in the future. Log on it anyway? (yes/[NO]) ", date_str).as_str()) == "yes",
+                days => matches!(Tui::get_user_input(format!("+ Log on {} ({} days ago)? ([YES]/no) ", date_str, days).as_str()).as_str(), "" | "y" | "yes"),
+            };
+            if confirmed {
+                return Some(date);
+            }
         }
-        return NaiveDate::from_ymd_opt(year, month, day);
     }
 
-    fn add_lifts(transaction: &mut Transaction, session_id: i32) -> Result<i32, Error> {
-        let mut added_lifts = 0;
+    // Lifts are kept in memory until the session is reviewed, then inserted into the transaction.
+    // False if the session was cancelled.
+    // The draft is saved after every change, so the lifts survive a crash before the commit.
+    fn add_lifts(transaction: &mut Transaction, session_id: i32, prescriptions: &[Prescription], unit: Unit, draft: &mut Draft) -> Result<bool, Error> {
+        println!("+ ... Enter adds a lift, l lists them, e/d N edits/deletes lift N, m N M moves it, u undoes the last, r reviews and logs, c cancels.");
+        // A resumed session starts with its lifts listed.
+        let mut add = draft.lifts.is_empty();
+        if !add {
+            Tui::print_pending_lifts(&draft.lifts);
+        }
         loop {
-            if Tui::dialogue_new_lift(transaction, session_id)? {
-                added_lifts += 1;
-            } else {
-                println!("+ ... Lift cancelled.");
+            if add {
+                match Tui::dialogue_new_lift(transaction, prescriptions, unit)? {
+                    Some(lifts) => {
+                        let exercise_id = lifts.first().map(|pending| pending.lift.exercise_id);
+                        draft.lifts.extend(lifts);
+                        if let Some(exercise_id) = exercise_id {
+                            Tui::dialogue_rest(transaction, exercise_id, draft)?;
+                        }
+                    },
+                    None => println!("+ ... Lift cancelled."),
+                }
+            }
+            add = false;
+            Tui::sync_draft(draft);
+            let pending = &mut draft.lifts;
+            let inp = Tui::get_user_input(format!("+ Session ({} lifts)# ", pending.len()).as_str());
+            let args: Vec<&str> = inp.split_whitespace().collect();
+            // Lift numbers start at 1 as listed.
+            let index = |arg: Option<&&str>| arg.and_then(|a| a.parse::<usize>().ok()).filter(|i| *i >= 1 && *i <= pending.len()).map(|i| i - 1);
+            match args.first().copied() {
+                None | Some("a") => add = true,
+                Some("l") => Tui::print_pending_lifts(pending),
+                Some("u") => match pending.pop() {
+                    Some(lift) => println!("+ ... Removed {}.", Tui::get_pending_lift_string(&lift)),
+                    None => println!("+ !!! No lifts yet."),
+                },
+                Some("d") => match index(args.get(1)) {
+                    Some(i) => println!("+ ... Removed {}.", Tui::get_pending_lift_string(&pending.remove(i))),
+                    None => println!("+ !!! Use d N with N from 1 to {}.", pending.len()),
+                },
+                Some("e") => match index(args.get(1)) {
+                    Some(i) => match Tui::dialogue_edit_pending_lift(&mut pending[i]) {
+                        true => println!("+ ... Now {}.", Tui::get_pending_lift_string(&pending[i])),
+                        false => println!("+ ... Edit cancelled."),
+                    },
+                    None => println!("+ !!! Use e N with N from 1 to {}.", pending.len()),
+                },
+                Some("m") => match (index(args.get(1)), index(args.get(2))) {
+                    (Some(from), Some(to)) => {
+                        let lift = pending.remove(from);
+                        pending.insert(to, lift);
+                        Tui::print_pending_lifts(pending);
+                    },
+                    _ => println!("+ !!! Use m N M with N and M from 1 to {}.", pending.len()),
+                },
+                Some("r") => {
+                    if pending.is_empty() {
+                        println!("+ !!! No lifts yet.");
+                        continue;
+                    }
+                    if Tui::dialogue_review_session(transaction, session_id, pending, unit)? {
+                        return Ok(true);
+                    }
+                },
+                Some("q") | Some("c") => {
+                    if pending.is_empty() || Tui::get_user_input(format!("+ Discard {} lifts? (yes/[NO]) ", pending.len()).as_str()) == "yes" {
+                        return Ok(false);
+                    }
+                },
+                Some(_) => println!("+ !!! Invalid input."),
+            }
+        }
+    }
+
+    // Runs the rest timer of the exercise, if it has one, and keeps the actual rest with the lift just added.
+    fn dialogue_rest(transaction: &mut Transaction, exercise_id: i32, draft: &mut Draft) -> Result<(), Error> {
+        let seconds = match Db::transaction_select_exercise_defaults(transaction, exercise_id)?.rest {
+            Some(seconds) if seconds > 0 => seconds,
+            _ => return Ok(()),
+        };
+        // The lift is kept before the wait, in case the terminal goes away meanwhile.
+        Tui::sync_draft(draft);
+        if let Some(rested) = timer::rest(seconds) {
+            println!("+ ... Rested {}.", timer::format(rested));
+            if let Some(pending) = draft.lifts.last_mut() {
+                pending.lift.rest = Some(rested);
             }
-            let inp = Tui::get_user_input("+ Add more lifts ([YES]/calcel) ? ");
-            if inp != "" {
-                break;
+        }
+        Ok(())
+    }
+
+    // Inserts the lifts within a savepoint to show the PRs they would set. Going back rolls them back again.
+    fn dialogue_review_session(transaction: &mut Transaction, session_id: i32, pending: &[PendingLift], unit: Unit) -> Result<bool, Error> {
+        println!("+ ... Review:");
+        Tui::print_pending_lifts(pending);
+        let mut savepoint = transaction.transaction()?;
+        for pending_lift in pending.iter() {
+            Db::transaction_insert_lift(&mut savepoint, session_id, &pending_lift.lift)?;
+        }
+        let prs = Db::transaction_select_session_prs(&mut savepoint, session_id)?;
+        if !prs.is_empty() {
+            println!("+ *** New PRs:");
+            for pr in &prs {
+                println!("+ *** {}", Tui::get_pr_string(pr, unit));
             }
         }
-        Ok(added_lifts)
+        match Tui::get_user_input("+ Log session? ([YES]/back) ").as_str() {
+            "" | "y" | "yes" => {
+                savepoint.commit()?;
+                Ok(true)
+            },
+            _ => Ok(false),
+        }
+    }
+
+    // False when cancelled, leaving the lift as it was.
+    fn dialogue_edit_pending_lift(pending_lift: &mut PendingLift) -> bool {
+        let lift = &pending_lift.lift;
+        let (weight, unit) = match Tui::get_user_input_weight(format!("+ Weight [{}] ({}): ", lift.unit.as_str(), lift.weight).as_str(), Some(lift.weight), lift.unit) {
+            Some(Some(weight)) => weight,
+            _ => return false,
+        };
+        let reps = match Tui::get_user_input_float(format!("+ Reps ({}): ", lift.reps).as_str(), Some(lift.reps)) {
+            Some(reps) => reps,
+            None => return false,
+        };
+        let sets = match Tui::get_user_input_float(format!("+ Sets ({}): ", lift.sets).as_str(), Some(lift.sets)) {
+            Some(sets) => sets,
+            None => return false,
+        };
+        let rpe = match Tui::get_user_input_optional_float("+ RPE, - for none", lift.rpe) {
+            Some(rpe) => rpe,
+            None => return false,
+        };
+        let lift = &mut pending_lift.lift;
+        (lift.weight, lift.unit, lift.reps, lift.sets, lift.rpe) = (weight, unit, reps, sets, rpe);
+        true
+    }
+
+    fn print_pending_lifts(pending: &[PendingLift]) {
+        if pending.is_empty() {
+            println!("+ [No lifts yet]");
+        }
+        for (index, lift) in pending.iter().enumerate() {
+            println!("+ {}) {}", index + 1, Tui::get_pending_lift_string(lift));
+        }
+    }
+
+    fn get_pending_lift_string(pending: &PendingLift) -> String {
+        let lift = &pending.lift;
+        let rpe = lift.rpe.map(|rpe| format!(" @{}", rpe)).unwrap_or_default();
+        let rest = lift.rest.map(|rest| format!(", rested {}", timer::format(rest))).unwrap_or_default();
+        format!("{} {}x{}x{}{}{}", pending.exercise_name, lift.unit.format(lift.unit.to_kg(lift.weight)), lift.reps, lift.sets, rpe, rest)
     }
 
-    fn dialogue_new_lift(transaction: &mut Transaction, session_id: i32) -> Result<bool, Error> {
+    // The lifts of one entry, more than one for rep lists like 5,5,4. None if cancelled.
+    fn dialogue_new_lift(transaction: &mut Transaction, prescriptions: &[Prescription], unit: Unit) -> Result<Option<Vec<PendingLift>>, Error> {
         let exercises = Db::transaction_select_exercises(transaction)?;
-        if exercises.len() == 0 {
+        if exercises.is_empty() {
             println!("+ !!! [No defined exercises]");
-            return Ok(false);
+            return Ok(None);
         }
+        let aliases = Db::transaction_select_exercise_aliases(transaction)?;
+        let tags = Db::transaction_select_exercise_tags(transaction)?;
 
-        let selected_exercise = match Tui::select_exercise(&exercises) {
-            Some(exercise) => exercise,
-            None => return Ok(false),
+        let (selected_exercise, entry) = match Tui::select_lift_entry(&exercises, &aliases, &tags, true) {
+            Some(selected) => selected,
+            None => return Ok(None),
         };
 
         println!("+ ... Selected '{}'.", selected_exercise.1);
 
-        let (weight_default, reps_default, sets_default) = match selected_exercise.0 {
-            5 => {
-                //Chinups
-                (Some(0.0), None, Some(1.0))
-            },
-            6 => {
-                // Clean
-                (None, Some(1.0), Some(1.0))
-            },
-            10 => {
-                // Snatch
-                (None, Some(1.0), Some(1.0))
-            },
-            _ => {
-                // Default
-                (None, Some(5.0), Some(1.0))
+        let defaults = Db::transaction_select_exercise_defaults(transaction, selected_exercise.0)?;
+        let last_lift = Db::transaction_select_last_lift(transaction, selected_exercise.0)?;
+        let prescription = prescriptions.iter().find(|p| p.exercise_id == selected_exercise.0);
+
+        if let Some(last) = &last_lift {
+            println!("+ ... Last time: {}x{}x{} on {} {}", unit.format(last.weight), last.reps, last.sets, last.date.weekday(), last.date.format("%d.%m."));
+        }
+
+        // Plan prescription first, then whatever was lifted last time, then the exercise defaults.
+        let (weight_default, reps_default, sets_default) = match (prescription, &last_lift) {
+            (Some(p), _) => {
+                let weight = p.weight.map(|w| Tui::round_prescription(w, unit));
+                let weight_str = match weight {
+                    Some(w) => unit.format(w),
+                    None => String::from("?"),
+                };
+                println!("+ ... Plan: {}x{}x{}", weight_str, Tui::get_optional_float_string(p.reps), p.sets);
+                (weight.or(defaults.weight), p.reps.or(defaults.reps), Some(p.sets as f32))
             },
+            (None, Some(last)) => (Some(last.weight), Some(last.reps), Some(last.sets)),
+            (None, None) => (defaults.weight, defaults.reps, defaults.sets),
+        };
+        let weight_label = match defaults.bodyweight {
+            true => "Added weight (negative for assistance)",
+            false => "Weight",
         };
 
+        // Defaults are in kg, prompts are in the display unit.
+        let weight_default = weight_default.map(|w| (unit.from_kg(w) * 100.0).round() / 100.0);
         let weight_def_str = match weight_default {
             None => String::new(),
             Some(f) => format!(" ({})", f).to_string(),
@@ -396,45 +919,107 @@ impl Tui<'_> {
             Some(f) => format!(" ({})", f).to_string(),
         };
 
-        let weight = match Tui::get_user_input_float(format!("+ Weight{}: ", weight_def_str).as_str(), weight_default) {
-            Some(f) => f,
-            None => return Ok(false),
+        // Whatever the one-line entry left out is asked for.
+        let (weight, weight_unit) = match entry.weight {
+            Some((w, entry_unit)) => (w, entry_unit.unwrap_or(unit)),
+            // Enter without a default asks again, only q/c cancel the lift.
+            None => loop {
+                match Tui::get_user_input_weight(format!("+ {} [{}]{}: ", weight_label, unit.as_str(), weight_def_str).as_str(), weight_default, unit) {
+                    Some(Some(w)) => break w,
+                    Some(None) => println!("+ !!! Invalid input."),
+                    None => return Ok(None),
+                }
+            },
         };
-        let reps = match Tui::get_user_input_float(format!("+ Reps{}: ", reps_def_str).as_str(), reps_default) {
-            Some(f) => f,
-            None => return Ok(false),
+        let rep_groups = match entry.reps.is_empty() {
+            false => entry.rep_groups(),
+            true => {
+                let reps = match Tui::get_user_input_float(format!("+ Reps{}: ", reps_def_str).as_str(), reps_default) {
+                    Some(f) => f,
+                    None => return Ok(None),
+                };
+                let sets = match Tui::get_user_input_float(format!("+ Sets{}: ", sets_def_str).as_str(), sets_default) {
+                    Some(f) => f,
+                    None => return Ok(None),
+                };
+                vec![(reps, sets)]
+            },
         };
-        let sets = match Tui::get_user_input_float(format!("+ Sets{}: ", sets_def_str).as_str(), sets_default) {
-            Some(f) => f,
-            None => return Ok(false),
+
+        // Kept so missed prescriptions can be spotted later. AMRAP sets have no rep target to miss.
+        let (prescribed_weight, prescribed_reps) = match prescription {
+            Some(p) => (p.weight.map(|w| Tui::round_prescription(w, unit)), p.reps.filter(|_| !p.amrap)),
+            None => (None, None),
         };
+        let mut lifts: Vec<PendingLift> = Vec::new();
+        for (reps, sets) in rep_groups {
+            let lift = NewLift {exercise_id: selected_exercise.0, weight, unit: weight_unit, reps, sets, prescribed_weight, prescribed_reps, notes: None, rpe: entry.rpe, rest: None};
+            lifts.push(PendingLift {exercise_name: selected_exercise.1.clone(), lift});
+            println!("+ ... Added {}", Tui::get_pending_lift_string(&lifts[lifts.len() - 1]));
+        }
 
-        Db::transaction_insert_lift(transaction, selected_exercise.0, session_id, weight, reps, sets)?;
-        
-        Ok(true)
+        Ok(Some(lifts))
+    }
+
+    fn get_optional_float_string(f: Option<f32>) -> String {
+        match f {
+            Some(f) => f.to_string(),
+            None => String::from("?"),
+        }
+    }
+
+    fn pick_exercise(&mut self) -> Result<Option<(i32, String)>, Error> {
+        let exercises = self.db.select_exercises()?;
+        let aliases = self.db.select_exercise_aliases()?;
+        let tags = self.db.select_exercise_tags()?;
+        Ok(Tui::select_exercise(&exercises, &aliases, &tags))
+    }
+
+    fn select_exercise(exercises: &[(i32, String)], aliases: &[(i32, String)], tags: &[(i32, String)]) -> Option<(i32, String)> {
+        Tui::select_lift_entry(exercises, aliases, tags, false).map(|(exercise, _)| exercise)
     }
 
-    fn select_exercise(exercises: &Vec<(i32, String)>) -> Option<(i32, String)> {
+    // With `entries` the exercise can be followed by the rest of a one-line entry such as "squat 100x5x3".
+    fn select_lift_entry(exercises: &[(i32, String)], aliases: &[(i32, String)], tags: &[(i32, String)], entries: bool) -> Option<((i32, String), LiftEntry)> {
         loop {
-            let inp = Tui::get_user_input("+ Exercise: ");
+            let inp = Tui::get_user_input_exercise("+ Exercise: ");
             if inp == "q" || inp == "c" {
                 return None;
             }
 
-            let possible_exercises: Vec<(i32, String)> = Tui::match_name_to_exercise(inp, &exercises);
-
-            if possible_exercises.len() > 1 {
-                println!("+ !!! Too many exercises match: {}", Tui::get_exercises_string(&possible_exercises));
+            if let Some(tag) = inp.strip_prefix('#') {
+                let tag = tag.to_lowercase();
+                let tagged: Vec<(i32, String)> = exercises.iter().filter(|exercise| tags.iter().any(|t| t.0 == exercise.0 && t.1 == tag)).cloned().collect();
+                match tagged.is_empty() {
+                    true => println!("+ !!! No exercises tagged '{}'.", tag),
+                    false => println!("+ ... #{}: {}", tag, Tui::get_exercises_string(&tagged)),
+                }
                 continue;
             }
-            match possible_exercises.get(0) {
-                Some(exercise) => return Some(exercise.clone()),
-                None => {println!("+ !!! No matching exercises. Known exercises: {}", Tui::get_exercises_string(&exercises)); continue;},
+
+            let entry = match entries {
+                true => match LiftEntry::parse(&inp) {
+                    Some(entry) => entry,
+                    None => {println!("+ !!! Invalid entry. Use e.g. 'squat 100x5x3', 'bench 80x5,5,4' or 'chin +10x8 @8'."); continue;},
+                },
+                false => LiftEntry {exercise: inp, ..LiftEntry::default()},
+            };
+            let possible_exercises: Vec<(i32, String)> = Tui::match_name_to_exercise(entry.exercise.clone(), exercises, aliases);
+
+            match possible_exercises.as_slice() {
+                [exercise] => return Some((exercise.clone(), entry)),
+                [] => println!("+ !!! {}", Tui::get_no_match_string(&entry.exercise, exercises, aliases)),
+                _ => {
+                    println!("+ ... '{}' matches several exercises:", entry.exercise);
+                    if let Some(exercise) = Tui::pick_exercise_from(&possible_exercises) {
+                        return Some((exercise, entry));
+                    }
+                },
             }
         }
     }
 
-    fn get_exercises_string(exercises: &Vec<(i32, String)>) -> String {
+    fn get_exercises_string(exercises: &[(i32, String)]) -> String {
         let mut ret = String::new();
         for (index, exercise) in exercises.iter().enumerate() {
             ret.push_str(exercise.1.as_str());
@@ -445,56 +1030,167 @@ impl Tui<'_> {
         ret
     }
 
-    fn match_name_to_exercise(inp: String, exercises: &Vec<(i32, String)>) -> Vec<(i32, String)> {
-        let mut ret: Vec<(i32, String)> = Vec::new();
-        if inp == "" {
-            return ret;
+    // Matches the input against exercise names and aliases, best first. Only the best kind of match counts:
+    // exact names, then prefixes, then word prefixes ("pulldown"), then substrings, then abbreviations ("fsq").
+    // So "Press" doesn't clash with "Press behind neck" and "sq" doesn't clash with "Front squat".
+    fn match_name_to_exercise(inp: String, exercises: &[(i32, String)], aliases: &[(i32, String)]) -> Vec<(i32, String)> {
+        if inp.is_empty() {
+            return Vec::new();
         }
-        for exercise in exercises {
-            if exercise.1.len() < inp.len() {
-                continue;
-            }
-            if inp.to_lowercase() == &exercise.1.to_lowercase()[..inp.len()] {
-                ret.push(exercise.clone());
+        let inp = inp.to_lowercase();
+        let mut ranked: Vec<(u8, usize, &(i32, String))> = Vec::new();
+        let names = exercises.iter().map(|exercise| (exercise.0, &exercise.1)).chain(aliases.iter().map(|alias| (alias.0, &alias.1)));
+        for (exercise_id, name) in names {
+            let (kind, score) = match Tui::match_rank(&inp, &name.to_lowercase()) {
+                Some(rank) => rank,
+                None => continue,
+            };
+            if let Some(exercise) = exercises.iter().find(|exercise| exercise.0 == exercise_id) {
+                ranked.push((kind, score, exercise));
             }
         }
+        ranked.sort_by_key(|r| (r.0, r.1));
 
+        let mut ret: Vec<(i32, String)> = Vec::new();
+        for (kind, _, exercise) in &ranked {
+            if *kind == ranked[0].0 && !ret.contains(exercise) {
+                ret.push((*exercise).clone());
+            }
+        }
         ret
     }
 
-    fn dialogue_get_pr(&mut self) -> Result<bool, Error> {
-        let exercises = self.db.select_exercises()?;
-        if exercises.len() == 0 {
-            println!("+ !!! [No defined exercises]");
-            return Ok(true);
+    // More matches than this are too vague to pick from, so only the count of the rest is shown.
+    const MATCH_LIMIT: usize = 8;
+
+    fn get_matches_string(matching: &[(i32, String)]) -> String {
+        let more = matching.len().saturating_sub(Tui::MATCH_LIMIT);
+        let shown = Tui::get_exercises_string(&matching[..matching.len() - more]);
+        match more {
+            0 => shown,
+            _ => format!("{} and {} more, type more letters", shown, more),
         }
+    }
 
-        let selected_exercise = match Tui::select_exercise(&exercises) {
-            Some(exercise) => exercise,
-            None => return Ok(false),
-        };
+    // Kind of match and a score within that kind, lower is better.
+    fn match_rank(inp: &str, name: &str) -> Option<(u8, usize)> {
+        if name == inp {
+            return Some((0, 0));
+        }
+        if name.starts_with(inp) {
+            return Some((1, name.len()));
+        }
+        if name.split(|c: char| !c.is_alphanumeric()).any(|word| word.starts_with(inp)) {
+            return Some((2, name.len()));
+        }
+        if name.contains(inp) {
+            return Some((3, name.len()));
+        }
+        // The letters in order, e.g. "fsq" for "front squat" or "dl" for "deadlift". They start at the start of a word and
+        // each one starts a word or follows the one before, but for one letter. Fewer such letters, then the sooner they're
+        // all found the better.
+        let inp: Vec<char> = inp.chars().filter(|c| !c.is_whitespace()).collect();
+        if inp.len() < 2 {
+            return None;
+        }
+        let name: Vec<char> = name.chars().collect();
+        let (skips, last) = Tui::match_abbreviation(&inp, &name, None)?;
+        (skips <= 1).then_some((4, skips * name.len() + last))
+    }
 
-        println!("+ ... Selected '{}'.", selected_exercise.1);
+    // The fewest letters that neither start a word nor follow the previous letter, and where the last letter is then.
+    fn match_abbreviation(inp: &[char], name: &[char], previous: Option<usize>) -> Option<(usize, usize)> {
+        let (c, rest) = match inp.split_first() {
+            Some(split) => split,
+            None => return Some((0, previous.unwrap_or(0))),
+        };
+        let from = previous.map_or(0, |p| p + 1);
+        let mut best: Option<(usize, usize)> = None;
+        for index in (from..name.len()).filter(|i| name[*i] == *c) {
+            let word_start = index == 0 || !name[index - 1].is_alphanumeric();
+            let skip = match previous {
+                None if !word_start => continue,
+                Some(p) if !word_start && p + 1 != index => 1,
+                _ => 0,
+            };
+            if let Some((skips, last)) = Tui::match_abbreviation(rest, name, Some(index)) {
+                let candidate = (skips + skip, last);
+                if best.is_none_or(|best| candidate < best) {
+                    best = Some(candidate);
+                }
+            }
+        }
+        best
+    }
 
-        let reps_default = match selected_exercise.0 {
-            5 => {
-                //Chinups
-                None
-            },
-            6 => {
-                // Clean
-                Some(1.0)
-            },
-            10 => {
-                // Snatch
-                Some(1.0)
-            },
-            _ => {
-                // Default
-                Some(5.0)
-            },
+    // Exercises whose name or alias is close to the input, for "did you mean" after a typo.
+    fn suggest_exercises(inp: &str, exercises: &[(i32, String)], aliases: &[(i32, String)]) -> Vec<(i32, String)> {
+        let inp = inp.to_lowercase();
+        let mut similar: Vec<(f64, &(i32, String))> = Vec::new();
+        let names = exercises.iter().map(|exercise| (exercise.0, &exercise.1)).chain(aliases.iter().map(|alias| (alias.0, &alias.1)));
+        for (exercise_id, name) in names {
+            let similarity = strsim::jaro_winkler(&inp, &name.to_lowercase());
+            if similarity < Tui::SUGGESTION_SIMILARITY {
+                continue;
+            }
+            if let Some(exercise) = exercises.iter().find(|exercise| exercise.0 == exercise_id) {
+                similar.push((similarity, exercise));
+            }
+        }
+        similar.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        let mut ret: Vec<(i32, String)> = Vec::new();
+        for (_, exercise) in similar {
+            if ret.len() < 3 && !ret.contains(exercise) {
+                ret.push(exercise.clone());
+            }
+        }
+        ret
+    }
+
+    const SUGGESTION_SIMILARITY: f64 = 0.8;
+
+    fn get_no_match_string(inp: &str, exercises: &[(i32, String)], aliases: &[(i32, String)]) -> String {
+        let suggestions = Tui::suggest_exercises(inp, exercises, aliases);
+        match suggestions.is_empty() {
+            true => format!("No exercise matches '{}'. Known exercises: {}", inp, Tui::get_exercises_string(exercises)),
+            false => format!("No exercise matches '{}'. Did you mean {}?", inp, suggestions.iter().map(|e| e.1.as_str()).collect::<Vec<&str>>().join(" or ")),
+        }
+    }
+
+    // Numbered list of ambiguous matches. None when the user would rather retype.
+    fn pick_exercise_from(matching: &[(i32, String)]) -> Option<(i32, String)> {
+        let more = matching.len().saturating_sub(Tui::MATCH_LIMIT);
+        let matching = &matching[..matching.len() - more];
+        for (index, exercise) in matching.iter().enumerate() {
+            println!("+ {}) {}", index + 1, exercise.1);
+        }
+        if more > 0 {
+            println!("+ ... and {} more, type more letters to narrow it down.", more);
+        }
+        let inp = Tui::get_user_input(format!("+ Pick 1-{} (empty to retype): ", matching.len()).as_str());
+        match inp.parse::<usize>() {
+            Ok(i) if i >= 1 && i <= matching.len() => Some(matching[i - 1].clone()),
+            _ => None,
+        }
+    }
+
+    fn dialogue_get_pr(&mut self) -> Result<bool, Error> {
+        let exercises = self.db.select_exercises()?;
+        if exercises.is_empty() {
+            println!("+ !!! [No defined exercises]");
+            return Ok(true);
+        }
+
+        let selected_exercise = match self.pick_exercise()? {
+            Some(exercise) => exercise,
+            None => return Ok(false),
         };
 
+        println!("+ ... Selected '{}'.", selected_exercise.1);
+
+        let reps_default = self.db.select_exercise_defaults(selected_exercise.0)?.pr_reps;
+
         let reps_def_str = match reps_default {
             None => String::new(),
             Some(f) => format!(" ({})", f).to_string(),
@@ -510,18 +1206,908 @@ impl Tui<'_> {
             Err(_) => {println!("[No such lifts found.]"); return Ok(true);}
         };
 
-        println!("{}: {}x{}", selected_exercise.1, pr_weight, reps);
+        let load_str = match self.db.select_exercise_defaults(selected_exercise.0)?.bodyweight {
+            true => " (total load)",
+            false => "",
+        };
+        println!("{}: {}x{}{}", selected_exercise.1, self.unit.format(pr_weight), reps, load_str);
+        if let Some((e1rm, date)) = self.db.select_exercise_e1rm_pr(selected_exercise.0)? {
+            println!("Best e1RM: {} on {} {}", self.unit.format(e1rm), date.weekday(), date.format("%d.%m.%Y"));
+        }
         
         Ok(true)
     }
 
-    fn dialogue_add_exercise(&mut self) -> Result<bool, Error> {
-        let exercise_name = Tui::get_user_input("Exercise name: ");
-        if exercise_name == "q" || exercise_name == "c" {
+    fn dialogue_pr_board(&mut self) -> Result<(), Error> {
+        let inp = Tui::get_user_input_exercise("PR board for exercise (empty for all): ");
+        let exercise_id = match inp.as_str() {
+            "q" | "c" => return Ok(()),
+            "" => None,
+            _ => {
+                let exercises = self.db.select_exercises()?;
+                let aliases = self.db.select_exercise_aliases()?;
+                let matching = Tui::match_name_to_exercise(inp.clone(), &exercises, &aliases);
+                match matching.as_slice() {
+                    [exercise] => Some(exercise.0),
+                    [] => {println!("!!! {}", Tui::get_no_match_string(&inp, &exercises, &aliases)); return Ok(());},
+                    _ => match Tui::pick_exercise_from(&matching) {
+                        Some(exercise) => Some(exercise.0),
+                        None => return Ok(()),
+                    },
+                }
+            },
+        };
+
+        let boards = self.db.select_pr_board(exercise_id)?;
+        self.print_pr_boards(&boards);
+        Ok(())
+    }
+
+    fn print_pr_boards(&self, boards: &[PrBoard]) {
+        if boards.is_empty() {
+            println!("[No lifts found.]");
+        }
+        let date_str = |date: &NaiveDate| date.format("%d.%m.%Y").to_string();
+        for board in boards {
+            println!("== {} ==", board.exercise_name);
+            println!("{:>14}  {:>10}  Date", "Reps", "Weight");
+            for max in &board.rep_maxes {
+                println!("{:>14}  {:>10}  {}", max.reps, self.unit.format(max.load), date_str(&max.date));
+            }
+            if let Some(e1rm) = &board.e1rm {
+                println!("{:>14}  {:>10}  {}", "e1RM", self.unit.format(e1rm.value), date_str(&e1rm.date));
+            }
+            if let Some(volume) = &board.set_volume {
+                println!("{:>14}  {:>10}  {}", "Set volume", self.unit.format(volume.value), date_str(&volume.date));
+            }
+            if let Some(volume) = &board.session_volume {
+                println!("{:>14}  {:>10}  {}", "Session volume", self.unit.format(volume.value), date_str(&volume.date));
+            }
+        }
+    }
+
+    fn dialogue_pr_timeline(&mut self) -> Result<(), Error> {
+        let prs = self.db.select_personal_records(None)?;
+        if prs.is_empty() {
+            println!("[No PRs yet.]");
+        }
+        for pr in &prs {
+            println!("{} {}", pr.date.format("%d.%m.%Y"), Tui::get_pr_string(pr, self.unit));
+        }
+        Ok(())
+    }
+
+    fn get_pr_string(pr: &PersonalRecord, unit: Unit) -> String {
+        match pr.kind {
+            PrKind::Weight => format!("{}: {}RM {} (was {})", pr.exercise_name, pr.reps, unit.format(pr.value), unit.format(pr.previous)),
+            PrKind::Reps => format!("{}: {} reps at {} (was {})", pr.exercise_name, pr.value, unit.format(pr.load), pr.previous),
+            PrKind::E1rm => format!("{}: e1RM {} (was {})", pr.exercise_name, unit.format(pr.value), unit.format(pr.previous)),
+            PrKind::Volume => format!("{}: volume {} (was {})", pr.exercise_name, unit.format(pr.value), unit.format(pr.previous)),
+        }
+    }
+
+    fn dialogue_stats(&mut self) -> Result<(), Error> {
+        let today = self.db.select_current_date()?;
+        let mut stats = StatsQuery {from: today - Duration::weeks(12), to: today, period: Period::Week, filter: ExerciseFilter::default()};
+        if !self.dialogue_stats_range(&mut stats, &today) {
+            return Ok(());
+        }
+        loop {
+            println!("-----");
+            println!("t) Tonnage\ng) Tonnage per category\nm) Sets per muscle group\ni) Intensity\nf) Frequency\ne) Exercise charts\nr) Change range\nb) Start training block");
+            let inp = Tui::get_user_input("Stats# ");
+            let unit = self.unit;
+            match inp.as_str() {
+                "q" => break,
+                "c" => break,
+                "g" => {
+                    let rows = self.db.select_tonnage_by_category(&stats)?;
+                    self.print_group_stats(&stats, &rows, |v| unit.format(v))?;
+                },
+                "t" => {
+                    let blocks = self.db.select_blocks()?;
+                    let rows = self.db.select_tonnage(&stats)?;
+                    if rows.is_empty() {
+                        println!("[No lifts in range.]");
+                    }
+                    println!("{:<24} {:>12} {:>8} {:>9}", "Period", "Tonnage", "Sets", "Sessions");
+                    for row in &rows {
+                        println!("{:<24} {:>12} {:>8} {:>9}", Tui::get_period_string(&blocks, stats.period, &row.period), unit.format(row.tonnage), row.sets, row.sessions);
+                    }
+                },
+                "m" => {
+                    let rows = self.db.select_sets_per_muscle(&stats)?;
+                    self.print_group_stats(&stats, &rows, |v| format!("{} sets", v))?;
+                },
+                "i" => {
+                    let rows = self.db.select_intensity(&stats)?;
+                    self.print_group_stats(&stats, &rows, |v| format!("{:.1}%", v))?;
+                },
+                "f" => {
+                    let rows = self.db.select_frequency(&stats)?;
+                    self.print_group_stats(&stats, &rows, |v| format!("{}x", v))?;
+                },
+                "e" => {
+                    let exercise = match self.pick_exercise()? {
+                        Some(e) => e,
+                        None => continue,
+                    };
+                    self.print_exercise_charts(&exercise, &stats.from, &stats.to)?;
+                },
+                "r" => {
+                    self.dialogue_stats_range(&mut stats, &today);
+                },
+                "b" => {
+                    let name = Tui::get_user_input("Block name: ");
+                    if name.is_empty() || name == "q" || name == "c" {
+                        continue;
+                    }
+                    let start = match self.get_user_input_date(format!("Block start ({}): ", self.date_order.format(&today)).as_str(), &today, &today) {
+                        Some(date) => date,
+                        None => continue,
+                    };
+                    self.db.insert_block(&name, &start)?;
+                    println!("Block '{}' starts on {}.", name, start.format("%d.%m.%Y"));
+                },
+                _ => println!("Invalid input."),
+            }
+        }
+        Ok(())
+    }
+
+    fn print_exercise_charts(&mut self, exercise: &(i32, String), from: &NaiveDate, to: &NaiveDate) -> Result<(), Error> {
+        let unit = self.unit;
+        let width = chart::terminal_width();
+        let format_y = |y: f32| unit.format(unit.to_kg(y));
+        let progress = self.db.select_exercise_progress(exercise.0, from, to)?;
+        let e1rms: Vec<(NaiveDate, f32)> = progress.iter().map(|p| (p.date, unit.from_kg(p.e1rm))).collect();
+        let top_sets: Vec<(NaiveDate, f32)> = progress.iter().map(|p| (p.date, unit.from_kg(p.top_load))).collect();
+        let volume: Vec<(NaiveDate, f32)> = self.db.select_exercise_weekly_volume(exercise.0, from, to)?.into_iter().map(|v| (v.0, unit.from_kg(v.1))).collect();
+
+        let charts = [
+            chart::line_chart(format!("{} e1RM", exercise.1).as_str(), &e1rms, width, &format_y),
+            chart::line_chart(format!("{} top set", exercise.1).as_str(), &top_sets, width, &format_y),
+            chart::line_chart(format!("{} weekly volume", exercise.1).as_str(), &volume, width, &format_y),
+        ];
+        for lines in charts {
+            for line in lines {
+                println!("{}", line);
+            }
+        }
+        Ok(())
+    }
+
+    fn dialogue_stats_range(&self, stats: &mut StatsQuery, today: &NaiveDate) -> bool {
+        stats.from = match self.get_user_input_date(format!("From ({}): ", self.date_order.format(&stats.from)).as_str(), &stats.from, today) {
+            Some(date) => date,
+            None => return false,
+        };
+        stats.to = match self.get_user_input_date(format!("To ({}): ", self.date_order.format(&stats.to)).as_str(), &stats.to, today) {
+            Some(date) => date,
+            None => return false,
+        };
+        stats.period = match Tui::get_user_input_choice(format!("Group by (week/month/block) ({}): ", stats.period.as_str()).as_str(), Some(stats.period), Period::parse) {
+            Some(Some(period)) => period,
+            Some(None) => Period::Week,
+            None => return false,
+        };
+        match Tui::get_user_input_filter() {
+            Some(filter) => {stats.filter = filter; true},
+            None => false,
+        }
+    }
+
+    fn get_user_input_filter() -> Option<ExerciseFilter> {
+        loop {
+            let inp = Tui::get_user_input("Filter (category/equipment/muscle/unilateral, empty for all): ");
+            match inp.as_str() {
+                "q" | "c" => return None,
+                "" => return Some(ExerciseFilter::default()),
+                term => match ExerciseFilter::parse(term) {
+                    Some(filter) => return Some(filter),
+                    None => println!("Unknown filter '{}'.", term),
+                },
+            }
+        }
+    }
+
+    fn get_user_input_date(&self, prompt: &str, default: &NaiveDate, today: &NaiveDate) -> Option<NaiveDate> {
+        loop {
+            let inp = Tui::get_user_input(prompt);
+            match inp.as_str() {
+                "q" | "c" => return None,
+                "" => return Some(*default),
+                _ => match liftsql::parse_date(&inp, today, self.date_order) {
+                    Some(date) => return Some(date),
+                    None => println!("Invalid input."),
+                },
+            }
+        }
+    }
+
+    fn get_period_string(blocks: &[(NaiveDate, String)], period: Period, start: &NaiveDate) -> String {
+        match period {
+            Period::Week => format!("Week of {}", start.format("%d.%m.%Y")),
+            Period::Month => start.format("%m/%Y").to_string(),
+            Period::Block => match blocks.iter().find(|block| block.0 == *start) {
+                Some(block) => format!("{} ({})", block.1, start.format("%d.%m.%Y")),
+                None => String::from("Before first block"),
+            },
+        }
+    }
+
+    fn print_group_stats<F: Fn(f32) -> String>(&mut self, stats: &StatsQuery, rows: &[GroupStats], format_value: F) -> Result<(), Error> {
+        let blocks = self.db.select_blocks()?;
+        if rows.is_empty() {
+            println!("[No lifts in range.]");
+        }
+        let mut last_period: Option<NaiveDate> = None;
+        for row in rows {
+            if last_period != Some(row.period) {
+                println!("{}", Tui::get_period_string(&blocks, stats.period, &row.period));
+                last_period = Some(row.period);
+            }
+            println!("  {:<24} {:>12}", row.group, format_value(row.value));
+        }
+        Ok(())
+    }
+
+    fn dialogue_export(&mut self) -> Result<bool, Box<dyn std::error::Error>> {
+        let today = self.db.select_current_date()?;
+        let first = self.db.select_first_session_date()?.unwrap_or(today);
+        let from = match self.get_user_input_date(format!("From ({}): ", self.date_order.format(&first)).as_str(), &first, &today) {
+            Some(date) => date,
+            None => return Ok(false),
+        };
+        let to = match self.get_user_input_date(format!("To ({}): ", self.date_order.format(&today)).as_str(), &today, &today) {
+            Some(date) => date,
+            None => return Ok(false),
+        };
+        let filter = match Tui::get_user_input_filter() {
+            Some(filter) => filter,
+            None => return Ok(false),
+        };
+        let exercise_ids = match self.get_user_input_exercise_ids()? {
+            Some(ids) => ids,
+            None => return Ok(false),
+        };
+        let unit = match Tui::get_user_input_choice(format!("Unit (kg/lb) ({}): ", self.unit.as_str()).as_str(), Some(self.unit), Unit::parse) {
+            Some(unit) => unit.unwrap_or(self.unit),
+            None => return Ok(false),
+        };
+        let rows = match Tui::get_user_input_choice("One row per (lift/set) (lift): ", Some(ExportRows::Lift), ExportRows::parse) {
+            Some(rows) => rows.unwrap_or(ExportRows::Lift),
+            None => return Ok(false),
+        };
+        let default_path = format!("liftsql-{}.csv", today.format("%Y-%m-%d"));
+        let path = match Tui::get_user_input(format!("File ({}): ", default_path).as_str()).as_str() {
+            "q" | "c" => return Ok(false),
+            "" => default_path,
+            path => path.to_string(),
+        };
+
+        let export = ExportQuery {from, to, filter, exercise_ids, unit, rows};
+        let file = BufWriter::new(File::create(&path)?);
+        let written = self.db.export_csv(&export, file)?;
+        println!("+ Exported {} rows to {}.", written, path);
+        Ok(true)
+    }
+
+    fn dialogue_import(&mut self) -> Result<bool, Box<dyn std::error::Error>> {
+        let path = match Tui::get_user_input("File: ").as_str() {
+            "" | "q" | "c" => return Ok(false),
+            path => path.to_string(),
+        };
+        let format = match Tui::get_user_input_choice("Format (csv/strong/hevy/fitnotes) (csv): ", None, App::parse) {
+            Some(Some(app)) => ImportFormat::App(app),
+            Some(None) => match Tui::get_user_input_column_mapping() {
+                Some((mapping, date_format)) => ImportFormat::Csv(mapping, date_format),
+                None => return Ok(false),
+            },
+            None => return Ok(false),
+        };
+        let unit = match Tui::get_user_input_choice(format!("Unit of weights without one (kg/lb) ({}): ", self.unit.as_str()).as_str(), Some(self.unit), Unit::parse) {
+            Some(unit) => unit.unwrap_or(self.unit),
+            None => return Ok(false),
+        };
+        let create_exercises = loop {
+            match Tui::get_user_input("Create missing exercises? (y/n) (y): ").as_str() {
+                "y" | "" => break true,
+                "n" => break false,
+                "q" | "c" => return Ok(false),
+                _ => println!("Invalid input."),
+            }
+        };
+
+        let report = self.import_file(&path, &format, unit, create_exercises, true)?;
+        println!("+ ... Dry run of {}:", path);
+        Tui::print_import_report(&report);
+        if !report.errors.is_empty() {
+            println!("+ !!! Fix the errors above before importing.");
+            return Ok(true);
+        }
+        if report.lifts == 0 || Tui::get_user_input("Import? (y/n): ") != "y" {
             return Ok(false);
         }
-        _ = self.db.insert_exercise(exercise_name)?;
+        let report = self.import_file(&path, &format, unit, create_exercises, false)?;
+        if report.committed {
+            println!("+ Imported {} lifts in {} sessions.", report.lifts, report.sessions_created + report.sessions_reused);
+        } else {
+            Tui::print_import_report(&report);
+            println!("+ !!! Nothing was imported.");
+        }
         Ok(true)
     }
 
+    fn get_user_input_column_mapping() -> Option<(ColumnMapping, Option<String>)> {
+        let defaults = ColumnMapping::default();
+        let mut columns: Vec<Option<String>> = Vec::new();
+        for (label, default, optional) in [("Date", Some(defaults.date), false), ("Exercise", Some(defaults.exercise), false), ("Weight", Some(defaults.weight), false), ("Reps", Some(defaults.reps), false), ("Sets", defaults.sets, true), ("Notes", defaults.notes, true)] {
+            let hint = if optional { ", - for none" } else { "" };
+            let inp = Tui::get_user_input(format!("{} column ({}{}): ", label, default.clone().unwrap_or_default(), hint).as_str());
+            columns.push(match inp.as_str() {
+                "q" | "c" => return None,
+                "" => default,
+                "-" if optional => None,
+                name => Some(name.to_string()),
+            });
+        }
+        let mut columns = columns.into_iter();
+        let mut next_column = || columns.next().flatten();
+        let mapping = ColumnMapping {
+            date: next_column().unwrap_or_default(),
+            exercise: next_column().unwrap_or_default(),
+            weight: next_column().unwrap_or_default(),
+            reps: next_column().unwrap_or_default(),
+            sets: next_column(),
+            notes: next_column(),
+        };
+        let date_format = match Tui::get_user_input("Date format (e.g. %d.%m.%Y, empty to detect): ").as_str() {
+            "q" | "c" => return None,
+            "" => None,
+            format => Some(format.to_string()),
+        };
+        Some((mapping, date_format))
+    }
+
+    fn import_file(&mut self, path: &str, format: &ImportFormat, unit: Unit, create_exercises: bool, dry_run: bool) -> Result<ImportReport, Box<dyn std::error::Error>> {
+        let file = File::open(path)?;
+        match format {
+            ImportFormat::Csv(mapping, date_format) => {
+                let options = ImportOptions {mapping: mapping.clone(), date_format: date_format.clone(), unit, create_exercises, dry_run};
+                self.db.import_csv(file, &options)
+            },
+            ImportFormat::App(app) => {
+                let options = AppImportOptions {app: Some(*app), unit, create_exercises, dry_run};
+                Ok(self.db.import_app_csv(file, &options)?.1)
+            },
+        }
+    }
+
+    fn print_import_report(report: &ImportReport) {
+        println!("+ ... {} rows, {} lifts, {} new sessions, {} existing sessions", report.rows, report.lifts, report.sessions_created, report.sessions_reused);
+        if !report.created_exercises.is_empty() {
+            println!("+ ... New exercises: {}", report.created_exercises.join(", "));
+        }
+        if !report.unknown_exercises.is_empty() {
+            println!("+ ... Unknown exercises: {}", report.unknown_exercises.join(", "));
+        }
+        if report.sessions_skipped > 0 {
+            println!("+ ... {} sessions were imported before and are skipped", report.sessions_skipped);
+        }
+        if report.lifts_skipped > 0 {
+            println!("+ ... {} lifts were logged on their date before and are skipped", report.lifts_skipped);
+        }
+        if report.rows_skipped > 0 {
+            println!("+ ... {} rows without a lift (warmups, rest timers, cardio) are skipped", report.rows_skipped);
+        }
+        for (app_name, name) in &report.mapped_exercises {
+            println!("+ ... {} -> {}", app_name, name);
+        }
+        for (line, err) in &report.errors {
+            println!("+ !!! Line {}: {}", line, err);
+        }
+    }
+
+    fn dialogue_backup(&mut self) -> Result<bool, Box<dyn std::error::Error>> {
+        let today = self.db.select_current_date()?;
+        let default_path = format!("liftsql-{}.json", today.format("%Y-%m-%d"));
+        let path = match Tui::get_user_input(format!("File ({}): ", default_path).as_str()).as_str() {
+            "q" | "c" => return Ok(false),
+            "" => default_path,
+            path => path.to_string(),
+        };
+        let config = self.read_config().unwrap_or(Config::default());
+        let settings: BTreeMap<String, String> = Tui::config_string(&config).lines().filter_map(|line| line.split_once('=')).map(|(key, value)| (key.to_string(), value.to_string())).collect();
+        let backup = self.db.select_backup(settings)?;
+        backup.write(BufWriter::new(File::create(&path)?))?;
+        let lifts: usize = backup.sessions.iter().map(|s| s.lifts.len()).sum();
+        println!("+ Backed up {} exercises, {} sessions and {} lifts to {}.", backup.exercises.len(), backup.sessions.len(), lifts, path);
+        Ok(true)
+    }
+
+    fn dialogue_restore(&mut self) -> Result<bool, Box<dyn std::error::Error>> {
+        let path = match Tui::get_user_input("File: ").as_str() {
+            "" | "q" | "c" => return Ok(false),
+            path => path.to_string(),
+        };
+        let backup = Backup::read(File::open(&path)?)?;
+        println!("+ ... Backup of {} (version {}): {} exercises, {} sessions", backup.created.format("%d.%m.%Y"), backup.version, backup.exercises.len(), backup.sessions.len());
+        if Tui::get_user_input("Restore? (y/n): ") != "y" {
+            return Ok(false);
+        }
+        let report = self.db.restore_backup(&backup)?;
+        println!("+ Restored {} sessions and {} lifts, {} new and {} existing exercises.", report.sessions, report.lifts, report.exercises_created, report.exercises_updated);
+        for conflict in &report.conflicts {
+            println!("+ !!! {}", conflict);
+        }
+
+        let settings: String = backup.settings.iter().map(|(key, value)| format!("{}={}\n", key, value)).collect();
+        match Tui::parse_config(&settings) {
+            Ok(config) => {
+                self.write_config(&config)?;
+                self.unit = config.unit;
+                self.stall_sessions = config.stall_sessions;
+                self.date_order = config.date_order;
+                println!("+ Restored settings.");
+            },
+            Err(err) => println!("+ !!! Settings not restored: {}", err),
+        }
+        Ok(true)
+    }
+
+    // Comma separated exercise names or aliases, archived exercises included. Empty for all exercises.
+    fn get_user_input_exercise_ids(&mut self) -> Result<Option<Vec<i32>>, Error> {
+        let mut exercises = self.db.select_exercises()?;
+        exercises.extend(self.db.select_archived_exercises()?);
+        let aliases = self.db.select_exercise_aliases()?;
+        'input: loop {
+            let inp = Tui::get_user_input_exercise("Exercises (comma separated, empty for all): ");
+            if inp == "q" || inp == "c" {
+                return Ok(None);
+            }
+            let mut ids: Vec<i32> = Vec::new();
+            for name in inp.split(',').map(|name| name.trim()).filter(|name| !name.is_empty()) {
+                let matches = Tui::match_name_to_exercise(name.to_string(), &exercises, &aliases);
+                match matches.as_slice() {
+                    [exercise] => ids.push(exercise.0),
+                    [] => {println!("{}", Tui::get_no_match_string(name, &exercises, &aliases)); continue 'input;},
+                    _ => match Tui::pick_exercise_from(&matches) {
+                        Some(exercise) => ids.push(exercise.0),
+                        None => continue 'input,
+                    },
+                }
+            }
+            return Ok(Some(ids));
+        }
+    }
+
+    fn dialogue_settings(&mut self) -> Result<(), io::Error> {
+        let mut config = match self.read_config() {
+            Ok(conf) => conf,
+            Err(err) => {println!("Error loading config: {}\nLoading default instead.", err); Config::default()}
+        };
+        config.unit = match Tui::get_user_input_choice(format!("Display unit (kg/lb) ({}): ", config.unit.as_str()).as_str(), Some(config.unit), Unit::parse) {
+            Some(unit) => unit.unwrap_or(config.unit),
+            None => return Ok(()),
+        };
+        loop {
+            match Tui::get_user_input_float(format!("Sessions without an e1RM PR before suggesting a deload ({}): ", config.stall_sessions).as_str(), Some(config.stall_sessions as f32)) {
+                Some(n) if n >= 1.0 => {config.stall_sessions = n as usize; break;},
+                Some(_) => println!("Invalid input."),
+                None => return Ok(()),
+            }
+        }
+        config.date_order = match Tui::get_user_input_choice(format!("Order of typed dates (dmy/mdy/ymd) ({}): ", config.date_order.as_str()).as_str(), Some(config.date_order), DateOrder::parse) {
+            Some(order) => order.unwrap_or(config.date_order),
+            None => return Ok(()),
+        };
+        self.write_config(&config)?;
+        self.unit = config.unit;
+        self.stall_sessions = config.stall_sessions;
+        self.date_order = config.date_order;
+        println!("Settings saved.");
+        Ok(())
+    }
+
+    fn dialogue_bodyweight(&mut self) -> Result<bool, Error> {
+        let date = self.db.select_current_date()?;
+        // Enter logs the last bodyweight again.
+        let last_bodyweight = self.db.select_bodyweight(&date)?;
+        let bodyweight_def_str = match last_bodyweight {
+            Some(w) => format!(" (last {})", self.unit.format(w)),
+            None => String::new(),
+        };
+        let default = last_bodyweight.map(|w| (self.unit.from_kg(w) * 100.0).round() / 100.0);
+        loop {
+            match Tui::get_user_input_weight(format!("Bodyweight [{}]{}: ", self.unit.as_str(), bodyweight_def_str).as_str(), default, self.unit) {
+                Some(Some((weight, unit))) if weight > 0.0 => {
+                    self.db.insert_bodyweight(&date, unit.to_kg(weight))?;
+                    return Ok(true);
+                },
+                Some(_) => println!("Invalid input."),
+                None => return Ok(false),
+            }
+        }
+    }
+
+    fn dialogue_add_exercise(&mut self) -> Result<bool, Error> {
+        let exercise_name = match self.get_new_exercise_name("Exercise name: ", None)? {
+            Some(name) => name,
+            None => return Ok(false),
+        };
+        self.db.insert_exercise(exercise_name)?;
+        Ok(true)
+    }
+
+    fn dialogue_exercise_defaults(&mut self, exercise_id: i32) -> Result<bool, Error> {
+        let mut defaults = self.db.select_exercise_defaults(exercise_id)?;
+        println!("Enter keeps the current value, '-' clears it.");
+        let unit = self.unit;
+        defaults.weight = match Tui::get_user_input_optional_float(format!("Default weight [{}]", unit.as_str()).as_str(), defaults.weight.map(|w| unit.from_kg(w))) {
+            Some(f) => f.map(|w| unit.to_kg(w)),
+            None => return Ok(false),
+        };
+        defaults.reps = match Tui::get_user_input_optional_float("Default reps", defaults.reps) {
+            Some(f) => f,
+            None => return Ok(false),
+        };
+        defaults.sets = match Tui::get_user_input_optional_float("Default sets", defaults.sets) {
+            Some(f) => f,
+            None => return Ok(false),
+        };
+        defaults.pr_reps = match Tui::get_user_input_optional_float("Reps for PRs", defaults.pr_reps) {
+            Some(f) => f,
+            None => return Ok(false),
+        };
+        defaults.rest = match Tui::get_user_input_optional_float("Rest between sets [s]", defaults.rest.map(|s| s as f32)) {
+            Some(f) => f.filter(|s| *s > 0.0).map(|s| s.round() as i32),
+            None => return Ok(false),
+        };
+        loop {
+            let current = match defaults.bodyweight {
+                true => "y",
+                false => "n",
+            };
+            let inp = Tui::get_user_input(format!("Bodyweight exercise (y/n) ({}): ", current).as_str());
+            match inp.as_str() {
+                "" => break,
+                "y" => {defaults.bodyweight = true; break;},
+                "n" => {defaults.bodyweight = false; break;},
+                "q" | "c" => return Ok(false),
+                _ => println!("Invalid input."),
+            }
+        }
+        self.db.update_exercise_defaults(exercise_id, &defaults)?;
+        Ok(true)
+    }
+
+    fn dialogue_exercise_attributes(&mut self, exercise_id: i32) -> Result<bool, Error> {
+        let mut attributes = self.db.select_exercise_attributes(exercise_id)?;
+        println!("Enter keeps the current value, '-' clears it.");
+
+        let categories: Vec<&str> = Category::ALL.iter().map(|c| c.as_str()).collect();
+        let current = attributes.category.map(|c| c.as_str()).unwrap_or("-");
+        attributes.category = match Tui::get_user_input_choice(format!("Category ({}) ({}): ", categories.join("/"), current).as_str(), attributes.category, Category::parse) {
+            Some(category) => category,
+            None => return Ok(false),
+        };
+
+        let equipment: Vec<&str> = Equipment::ALL.iter().map(|e| e.as_str()).collect();
+        let current = attributes.equipment.map(|e| e.as_str()).unwrap_or("-");
+        attributes.equipment = match Tui::get_user_input_choice(format!("Equipment ({}) ({}): ", equipment.join("/"), current).as_str(), attributes.equipment, Equipment::parse) {
+            Some(equipment) => equipment,
+            None => return Ok(false),
+        };
+
+        println!("Muscle groups: {}", MUSCLE_GROUPS.join(", "));
+        attributes.primary_muscles = match Tui::get_user_input_muscles("Primary muscles", &attributes.primary_muscles) {
+            Some(muscles) => muscles,
+            None => return Ok(false),
+        };
+        attributes.secondary_muscles = match Tui::get_user_input_muscles("Secondary muscles", &attributes.secondary_muscles) {
+            Some(muscles) => muscles.into_iter().filter(|m| !attributes.primary_muscles.contains(m)).collect(),
+            None => return Ok(false),
+        };
+
+        loop {
+            let current = match attributes.unilateral {
+                true => "y",
+                false => "n",
+            };
+            let inp = Tui::get_user_input(format!("Unilateral (y/n) ({}): ", current).as_str());
+            match inp.as_str() {
+                "" => break,
+                "y" => {attributes.unilateral = true; break;},
+                "n" => {attributes.unilateral = false; break;},
+                "q" | "c" => return Ok(false),
+                _ => println!("Invalid input."),
+            }
+        }
+
+        self.db.update_exercise_attributes(exercise_id, &attributes)?;
+        Ok(true)
+    }
+
+    fn get_user_input_choice<T>(prompt: &str, current: Option<T>, parse: fn(&str) -> Option<T>) -> Option<Option<T>> {
+        loop {
+            let inp = Tui::get_user_input(prompt);
+            match inp.as_str() {
+                "q" | "c" => return None,
+                "" => return Some(current),
+                "-" => return Some(None),
+                _ => match parse(&inp) {
+                    Some(t) => return Some(Some(t)),
+                    None => println!("Invalid input."),
+                },
+            }
+        }
+    }
+
+    fn get_user_input_muscles(label: &str, current: &[String]) -> Option<Vec<String>> {
+        let current_str = match current.is_empty() {
+            true => String::from("-"),
+            false => current.join(", "),
+        };
+        loop {
+            let inp = Tui::get_user_input(format!("{} ({}): ", label, current_str).as_str());
+            match inp.as_str() {
+                "q" | "c" => return None,
+                "" => return Some(current.to_vec()),
+                "-" => return Some(Vec::new()),
+                _ => {
+                    let mut muscles: Vec<String> = Vec::new();
+                    for name in inp.split(',').map(|m| m.trim()).filter(|m| !m.is_empty()) {
+                        match MUSCLE_GROUPS.iter().find(|m| m.eq_ignore_ascii_case(name)) {
+                            Some(muscle) => muscles.push(muscle.to_string()),
+                            None => println!("Unknown muscle group '{}'.", name),
+                        }
+                    }
+                    if muscles.len() == inp.split(',').filter(|m| !m.trim().is_empty()).count() {
+                        return Some(muscles);
+                    }
+                },
+            }
+        }
+    }
+
+    fn dialogue_show_exercises(&mut self) -> Result<(), Error> {
+        let inp = Tui::get_user_input("Filter (category/equipment/muscle/unilateral, empty for all): ");
+        let filter = match inp.as_str() {
+            "q" | "c" => return Ok(()),
+            "" => ExerciseFilter::default(),
+            term => match ExerciseFilter::parse(term) {
+                Some(filter) => filter,
+                None => {println!("Unknown filter '{}'.", term); return Ok(());},
+            },
+        };
+        let exercises = self.db.select_exercises_filtered(&filter)?;
+        let aliases = self.db.select_exercise_aliases()?;
+        let mut listed: Vec<(Option<Category>, String)> = Vec::new();
+        for exercise in &exercises {
+            let attributes = self.db.select_exercise_attributes(exercise.0)?;
+            let mut line = exercise.1.clone();
+            let exercise_aliases: Vec<&str> = aliases.iter().filter(|alias| alias.0 == exercise.0).map(|alias| alias.1.as_str()).collect();
+            if !exercise_aliases.is_empty() {
+                line.push_str(format!(" ({})", exercise_aliases.join(", ")).as_str());
+            }
+            if let Some(equipment) = attributes.equipment {
+                line.push_str(format!(" [{}]", equipment.as_str()).as_str());
+            }
+            if attributes.unilateral {
+                line.push_str(" [unilateral]");
+            }
+            if !attributes.primary_muscles.is_empty() {
+                line.push_str(format!(" {}", attributes.primary_muscles.join(", ")).as_str());
+            }
+            if !attributes.secondary_muscles.is_empty() {
+                line.push_str(format!(" ({})", attributes.secondary_muscles.join(", ")).as_str());
+            }
+            listed.push((attributes.category, line));
+        }
+        if listed.is_empty() {
+            println!("[No matching exercises]");
+        }
+        for category in Category::ALL.iter().map(Some).chain([None]) {
+            let lines: Vec<&String> = listed.iter().filter(|l| l.0.as_ref() == category).map(|l| &l.1).collect();
+            if lines.is_empty() {
+                continue;
+            }
+            println!("{}:", category.map(|c| c.as_str()).unwrap_or("uncategorized"));
+            for line in lines {
+                println!("  {}", line);
+            }
+        }
+        Ok(())
+    }
+
+    // `renaming` is the exercise the name is for, if it already exists.
+    fn get_new_exercise_name(&mut self, prompt: &str, renaming: Option<i32>) -> Result<Option<String>, Error> {
+        loop {
+            let name = Tui::get_user_input(prompt);
+            if name == "q" || name == "c" {
+                return Ok(None);
+            }
+            if let Err(err) = liftsql::check_exercise_name(&name) {
+                println!("!!! Invalid name: {}.", err);
+                continue;
+            }
+            if self.db.select_exercise_name_taken(&name, renaming)? {
+                println!("!!! '{}' is already used as an exercise name or alias.", name);
+                continue;
+            }
+            return Ok(Some(name));
+        }
+    }
+
+    fn dialogue_edit_exercises(&mut self) -> Result<(), Error> {
+        loop {
+            println!("-----");
+            println!("r) Rename\na) Archive\nu) Unarchive\nm) Merge\nl) Add alias\nd) Delete alias\nf) Set defaults\nt) Set attributes\ns) Show exercises");
+            let inp = Tui::get_user_input("Exercises# ");
+            match inp.as_str() {
+                "q" => break,
+                "c" => break,
+                "r" => {
+                    let exercise = match self.pick_exercise()? {
+                        Some(e) => e,
+                        None => continue,
+                    };
+                    let new_name = match self.get_new_exercise_name(format!("New name for '{}': ", exercise.1).as_str(), Some(exercise.0))? {
+                        Some(name) => name,
+                        None => continue,
+                    };
+                    self.db.rename_exercise(exercise.0, &new_name)?;
+                    println!("Renamed '{}' to '{}'.", exercise.1, new_name);
+                },
+                "a" => {
+                    let exercise = match self.pick_exercise()? {
+                        Some(e) => e,
+                        None => continue,
+                    };
+                    self.db.set_exercise_archived(exercise.0, true)?;
+                    println!("Archived '{}'. Its lifts are kept.", exercise.1);
+                },
+                "u" => {
+                    let archived = self.db.select_archived_exercises()?;
+                    if archived.is_empty() {
+                        println!("[No archived exercises]");
+                        continue;
+                    }
+                    println!("Archived: {}", Tui::get_exercises_string(&archived));
+                    let exercise = match Tui::select_exercise(&archived, &[], &[]) {
+                        Some(e) => e,
+                        None => continue,
+                    };
+                    self.db.set_exercise_archived(exercise.0, false)?;
+                    println!("Unarchived '{}'.", exercise.1);
+                },
+                "m" => {
+                    println!("Merge from:");
+                    let from = match self.pick_exercise()? {
+                        Some(e) => e,
+                        None => continue,
+                    };
+                    println!("Merge '{}' into:", from.1);
+                    let into = match self.pick_exercise()? {
+                        Some(e) => e,
+                        None => continue,
+                    };
+                    if from.0 == into.0 {
+                        println!("!!! Can't merge an exercise into itself.");
+                        continue;
+                    }
+                    if !Tui::get_user_input(format!("Move all lifts of '{}' to '{}' and delete '{}'? ([YES]/cancel) ", from.1, into.1, from.1).as_str()).is_empty() {
+                        continue;
+                    }
+                    self.db.merge_exercises(from.0, into.0)?;
+                    println!("Merged '{}' into '{}'. '{}' is kept as an alias.", from.1, into.1, from.1);
+                },
+                "l" => {
+                    let exercise = match self.pick_exercise()? {
+                        Some(e) => e,
+                        None => continue,
+                    };
+                    let alias = match self.get_new_exercise_name(format!("Alias for '{}': ", exercise.1).as_str(), None)? {
+                        Some(name) => name,
+                        None => continue,
+                    };
+                    self.db.insert_exercise_alias(exercise.0, &alias)?;
+                    println!("'{}' now also matches '{}'.", alias, exercise.1);
+                },
+                "d" => {
+                    let alias = Tui::get_user_input_exercise("Alias to delete: ");
+                    if alias == "q" || alias == "c" {
+                        continue;
+                    }
+                    match self.db.delete_exercise_alias(&alias)? {
+                        true => println!("Alias '{}' deleted.", alias),
+                        false => println!("!!! No alias '{}'.", alias),
+                    }
+                },
+                "f" => {
+                    let exercise = match self.pick_exercise()? {
+                        Some(e) => e,
+                        None => continue,
+                    };
+                    match self.dialogue_exercise_defaults(exercise.0)? {
+                        true => println!("Defaults for '{}' saved.", exercise.1),
+                        false => println!("Editing defaults cancelled."),
+                    }
+                },
+                "t" => {
+                    let exercise = match self.pick_exercise()? {
+                        Some(e) => e,
+                        None => continue,
+                    };
+                    match self.dialogue_exercise_attributes(exercise.0)? {
+                        true => println!("Attributes for '{}' saved.", exercise.1),
+                        false => println!("Editing attributes cancelled."),
+                    }
+                },
+                "s" => {
+                    self.dialogue_show_exercises()?;
+                },
+                _ => println!("Invalid input."),
+            };
+        }
+        Ok(())
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exercises(names: &[&str]) -> Vec<(i32, String)> {
+        names.iter().enumerate().map(|(id, name)| (id as i32, name.to_string())).collect()
+    }
+
+    fn matching(inp: &str, exercises: &[(i32, String)]) -> Vec<String> {
+        Tui::match_name_to_exercise(inp.to_string(), exercises, &[]).into_iter().map(|exercise| exercise.1).collect()
+    }
+
+    #[test]
+    fn prefers_exact_names_and_prefixes() {
+        let exercises = exercises(&["Press", "Press behind neck", "Squat", "Front squat"]);
+        assert_eq!(matching("press", &exercises), vec!["Press"]);
+        assert_eq!(matching("sq", &exercises), vec!["Squat"]);
+        assert_eq!(matching("pre", &exercises), vec!["Press", "Press behind neck"]);
+    }
+
+    #[test]
+    fn matches_word_prefixes_and_substrings() {
+        let exercises = exercises(&["Lat pulldown", "Deadlift", "Romanian deadlift"]);
+        assert_eq!(matching("pulldown", &exercises), vec!["Lat pulldown"]);
+        assert_eq!(matching("lift", &exercises), vec!["Deadlift", "Romanian deadlift"]);
+    }
+
+    #[test]
+    fn matches_abbreviations() {
+        let exercises = exercises(&["Front squat", "Squat", "Deadlift", "Overhead press", "Bench press"]);
+        assert_eq!(matching("fsq", &exercises), vec!["Front squat"]);
+        assert_eq!(matching("dl", &exercises), vec!["Deadlift"]);
+        assert_eq!(matching("ohp", &exercises), vec!["Overhead press"]);
+        assert_eq!(matching("bp", &exercises), vec!["Bench press"]);
+    }
+
+    #[test]
+    fn rejects_scattered_letters() {
+        let exercises = exercises(&["Front squat", "Barbell hip thrust", "Chest supported row"]);
+        assert!(matching("fqt", &exercises).is_empty());
+        assert!(matching("arw", &exercises).is_empty());
+        assert!(matching("ctw", &exercises).is_empty());
+        assert_eq!(matching("bht", &exercises), vec!["Barbell hip thrust"]);
+    }
+
+    #[test]
+    fn counts_the_matches_beyond_the_limit() {
+        let names: Vec<String> = (1..=20).map(|i| format!("Curl {}", i)).collect();
+        let exercises = exercises(&names.iter().map(String::as_str).collect::<Vec<&str>>());
+        let matching = Tui::match_name_to_exercise("curl".to_string(), &exercises, &[]);
+        assert_eq!(matching.len(), 20);
+        assert!(Tui::get_matches_string(&matching).ends_with("Curl 8 and 12 more, type more letters"));
+        assert_eq!(Tui::get_matches_string(&matching[..2]), "Curl 1; Curl 2");
+    }
 }
@@ -0,0 +1,149 @@
+use std::cell::RefCell;
+use std::env;
+use std::io::{self, IsTerminal, Write};
+use std::path::PathBuf;
+
+use rustyline::completion::{Completer, Pair};
+use rustyline::config::{CompletionType, Config};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::{DefaultHistory, FileHistory};
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+
+const HISTORY_FILE: &str = ".config/.liftsql_history";
+const HISTORY_SIZE: usize = 1000;
+
+// Completes exercise names and aliases from the start of the line.
+struct ExerciseCompleter {
+    names: Vec<String>,
+}
+
+impl Completer for ExerciseCompleter {
+    type Candidate = Pair;
+
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let prefix = line[..pos].to_lowercase();
+        let candidates = self.names.iter()
+            .filter(|name| name.to_lowercase().starts_with(&prefix))
+            .map(|name| Pair {display: name.clone(), replacement: name.clone()})
+            .collect();
+        Ok((0, candidates))
+    }
+}
+
+impl Hinter for ExerciseCompleter {
+    type Hint = String;
+}
+
+impl Highlighter for ExerciseCompleter {}
+
+impl Validator for ExerciseCompleter {}
+
+impl Helper for ExerciseCompleter {}
+
+thread_local! {
+    // None until the first prompt, and when stdin isn't a terminal. Only exercise prompts complete
+    // names and remember answers, other prompts still get line editing.
+    static EDITOR: RefCell<Option<Editor<ExerciseCompleter, FileHistory>>> = const { RefCell::new(None) };
+    static PLAIN_EDITOR: RefCell<Option<Editor<(), DefaultHistory>>> = const { RefCell::new(None) };
+    static NAMES: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+fn history_path() -> Option<PathBuf> {
+    let mut path = env::home_dir()?;
+    path.push(HISTORY_FILE);
+    Some(path)
+}
+
+fn new_editor() -> Option<Editor<ExerciseCompleter, FileHistory>> {
+    let config = Config::builder().max_history_size(HISTORY_SIZE).ok()?.history_ignore_dups(true).ok()?.completion_type(CompletionType::List).build();
+    let mut editor = Editor::with_config(config).ok()?;
+    editor.set_helper(Some(ExerciseCompleter {names: NAMES.with(|names| names.borrow().clone())}));
+    if let Some(path) = history_path() {
+        // There is no history file before the first run.
+        let _ = editor.load_history(&path);
+    }
+    Some(editor)
+}
+
+// Exercise names and aliases offered by tab completion.
+pub fn set_completions(mut names: Vec<String>) {
+    names.sort_by_key(|name| name.to_lowercase());
+    names.dedup();
+    EDITOR.with(|editor| {
+        if let Some(helper) = editor.borrow_mut().as_mut().and_then(|editor| editor.helper_mut()) {
+            helper.names = names.clone();
+        }
+    });
+    NAMES.with(|current| *current.borrow_mut() = names);
+}
+
+fn new_plain_editor() -> Option<Editor<(), DefaultHistory>> {
+    let config = Config::builder().auto_add_history(false).build();
+    Editor::with_config(config).ok()
+}
+
+// Ctrl-C answers "c" and Ctrl-D "q", so both cancel like typing them would.
+fn answer(line: rustyline::Result<String>) -> String {
+    match line {
+        Ok(line) => line.trim().to_string(),
+        Err(ReadlineError::Interrupted) => String::from("c"),
+        Err(ReadlineError::Eof) => String::from("q"),
+        Err(err) => panic!("Failed to read line: {}", err),
+    }
+}
+
+// Reads a trimmed line. Piped input is read as it is, without prompts being edited.
+pub fn read_line(prompt: &str) -> String {
+    if !io::stdin().is_terminal() {
+        return read_line_plain(prompt);
+    }
+    PLAIN_EDITOR.with(|cell| {
+        let mut cell = cell.borrow_mut();
+        if cell.is_none() {
+            *cell = new_plain_editor();
+        }
+        match cell.as_mut() {
+            Some(editor) => answer(editor.readline(prompt)),
+            None => read_line_plain(prompt),
+        }
+    })
+}
+
+// Like read_line, with tab completion of exercise names and the answers of earlier exercise prompts
+// on the arrow keys.
+pub fn read_line_exercise(prompt: &str) -> String {
+    if !io::stdin().is_terminal() {
+        return read_line_plain(prompt);
+    }
+    EDITOR.with(|cell| {
+        let mut cell = cell.borrow_mut();
+        if cell.is_none() {
+            *cell = new_editor();
+        }
+        let editor = match cell.as_mut() {
+            Some(editor) => editor,
+            None => return read_line_plain(prompt),
+        };
+        let line = answer(editor.readline(prompt));
+        if !line.is_empty() && line != "q" && line != "c" {
+            let _ = editor.add_history_entry(line.as_str());
+            if let Some(path) = history_path() {
+                if let Err(err) = editor.save_history(&path) {
+                    println!("ERROR SAVING PROMPT HISTORY: {}", err);
+                }
+            }
+        }
+        line
+    })
+}
+
+fn read_line_plain(prompt: &str) -> String {
+    print!("{}", prompt);
+    io::stdout().flush().expect("failed to flush buffer.");
+    let mut buffer: String = String::new();
+    io::stdin().read_line(&mut buffer).expect("Failed to read line.");
+    String::from(buffer.trim())
+}
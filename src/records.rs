@@ -0,0 +1,307 @@
+use std::collections::HashMap;
+
+use postgres::{Error, Row, Transaction};
+use chrono::NaiveDate;
+use serde::Serialize;
+
+use crate::Db;
+
+#[derive(Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PrKind {
+    // Heaviest load for the rep count.
+    Weight,
+    // Most reps at this load or heavier.
+    Reps,
+    E1rm,
+    // load x reps x sets of a single lift.
+    Volume,
+}
+
+impl PrKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PrKind::Weight => "weight",
+            PrKind::Reps => "reps",
+            PrKind::E1rm => "e1rm",
+            PrKind::Volume => "volume",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<PrKind> {
+        [PrKind::Weight, PrKind::Reps, PrKind::E1rm, PrKind::Volume].into_iter().find(|k| k.as_str() == s)
+    }
+}
+
+#[derive(Serialize)]
+pub struct PersonalRecord {
+    pub lift_id: i32,
+    pub exercise_name: String,
+    pub date: NaiveDate,
+    pub kind: PrKind,
+    pub load: f32,
+    pub reps: f32,
+    pub value: f32,
+    pub previous: f32,
+}
+
+// Heaviest lift for a rep count.
+#[derive(Serialize)]
+pub struct RepMax {
+    pub reps: f32,
+    pub load: f32,
+    pub date: NaiveDate,
+}
+
+#[derive(Serialize)]
+pub struct BoardValue {
+    pub value: f32,
+    pub date: NaiveDate,
+}
+
+#[derive(Serialize)]
+pub struct PrBoard {
+    pub exercise_id: i32,
+    pub exercise_name: String,
+    // One for each rep count from 1 to 12 that was lifted.
+    pub rep_maxes: Vec<RepMax>,
+    pub e1rm: Option<BoardValue>,
+    pub set_volume: Option<BoardValue>,
+    pub session_volume: Option<BoardValue>,
+}
+
+// A lift as PRs are compared, all values as the lift_loads view computes them.
+struct PrLift {
+    id: i32,
+    exercise_id: i32,
+    session_id: i32,
+    date: NaiveDate,
+    load: f32,
+    reps: f32,
+    e1rm: f32,
+    volume: f32,
+}
+
+// The best values of a set of lifts that PRs are compared against.
+#[derive(Default)]
+struct PrHistory {
+    // Heaviest load by rep count, keyed by the bits of the reps.
+    loads: HashMap<u32, f32>,
+    // (load, reps) of the lifts no other lift beats in both, for the most reps at a load or heavier.
+    frontier: Vec<(f32, f32)>,
+    e1rm: Option<f32>,
+    volume: Option<f32>,
+}
+
+fn max_option(a: Option<f32>, b: Option<f32>) -> Option<f32> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.max(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+impl PrHistory {
+    fn add(&mut self, lift: &PrLift) {
+        let load = self.loads.entry(lift.reps.to_bits()).or_insert(lift.load);
+        *load = load.max(lift.load);
+        if !self.frontier.iter().any(|(load, reps)| *load >= lift.load && *reps >= lift.reps) {
+            self.frontier.retain(|(load, reps)| !(lift.load >= *load && lift.reps >= *reps));
+            self.frontier.push((lift.load, lift.reps));
+        }
+        self.e1rm = max_option(self.e1rm, Some(lift.e1rm));
+        self.volume = max_option(self.volume, Some(lift.volume));
+    }
+
+    // Best weight, reps, e1RM and volume to beat, in the order of PrKind.
+    fn previous(&self, lift: &PrLift) -> [Option<f32>; 4] {
+        let reps = self.frontier.iter().filter(|(load, _)| *load >= lift.load).map(|(_, reps)| *reps).reduce(f32::max);
+        [self.loads.get(&lift.reps.to_bits()).copied(), reps, self.e1rm, self.volume]
+    }
+}
+
+impl Db {
+    // PR board of every exercise with lifts, or just the given one. All weights are total load in kg.
+    pub fn select_pr_board(&mut self, exercise_id: Option<i32>) -> Result<Vec<PrBoard>, Error> {
+        let mut ret: Vec<PrBoard> = Vec::new();
+        let exercises = self.client.query("SELECT id, name FROM exercises e WHERE NOT archived AND ($1::INT IS NULL OR id=$1) AND EXISTS (SELECT 1 FROM lifts l WHERE l.exercise_id=e.id) ORDER BY id;", &[&exercise_id])?;
+        for exercise in exercises {
+            ret.push(PrBoard {exercise_id: exercise.get(0), exercise_name: exercise.get(1), rep_maxes: Vec::new(), e1rm: None, set_volume: None, session_volume: None});
+        }
+
+        let rep_maxes = self.client.query("SELECT DISTINCT ON (exercise_id, reps) exercise_id, reps, load, date FROM lift_loads WHERE ($1::INT IS NULL OR exercise_id=$1) AND reps BETWEEN 1 AND 12 AND reps=floor(reps) ORDER BY exercise_id, reps, load DESC, date;", &[&exercise_id])?;
+        for row in rep_maxes {
+            if let Some(board) = ret.iter_mut().find(|b| b.exercise_id == row.get::<_, i32>(0)) {
+                board.rep_maxes.push(RepMax {reps: row.get(1), load: row.get(2), date: row.get(3)});
+            }
+        }
+
+        let e1rms = self.client.query("SELECT DISTINCT ON (exercise_id) exercise_id, e1rm, date FROM lift_loads WHERE ($1::INT IS NULL OR exercise_id=$1) AND e1rm > 0 ORDER BY exercise_id, e1rm DESC, date;", &[&exercise_id])?;
+        for row in e1rms {
+            if let Some(board) = ret.iter_mut().find(|b| b.exercise_id == row.get::<_, i32>(0)) {
+                board.e1rm = Some(BoardValue {value: row.get(1), date: row.get(2)});
+            }
+        }
+
+        let set_volumes = self.client.query("SELECT DISTINCT ON (exercise_id) exercise_id, (load*reps)::REAL AS volume, date FROM lift_loads WHERE ($1::INT IS NULL OR exercise_id=$1) AND load*reps > 0 ORDER BY exercise_id, volume DESC, date;", &[&exercise_id])?;
+        for row in set_volumes {
+            if let Some(board) = ret.iter_mut().find(|b| b.exercise_id == row.get::<_, i32>(0)) {
+                board.set_volume = Some(BoardValue {value: row.get(1), date: row.get(2)});
+            }
+        }
+
+        let session_volumes = self.client.query("SELECT DISTINCT ON (exercise_id) exercise_id, volume, date FROM (SELECT exercise_id, date, SUM(load*reps*sets)::REAL AS volume FROM lift_loads WHERE ($1::INT IS NULL OR exercise_id=$1) GROUP BY exercise_id, session_id, date) v WHERE volume > 0 ORDER BY exercise_id, volume DESC, date;", &[&exercise_id])?;
+        for row in session_volumes {
+            if let Some(board) = ret.iter_mut().find(|b| b.exercise_id == row.get::<_, i32>(0)) {
+                board.session_volume = Some(BoardValue {value: row.get(1), date: row.get(2)});
+            }
+        }
+
+        Ok(ret)
+    }
+
+    pub fn select_personal_records(&mut self, exercise_id: Option<i32>) -> Result<Vec<PersonalRecord>, Error> {
+        let query = self.client.query(format!("{} WHERE ($1::INT IS NULL OR l.exercise_id=$1) ORDER BY l.date, l.id, p.id;", Db::PERSONAL_RECORDS_QUERY).as_str(), &[&exercise_id])?;
+        Ok(query.iter().map(Db::row_to_personal_record).collect())
+    }
+
+    pub fn transaction_select_session_prs(transaction: &mut Transaction, session_id: i32) -> Result<Vec<PersonalRecord>, Error> {
+        let query = transaction.query(format!("{} WHERE l.session_id=$1 ORDER BY l.id, p.id;", Db::PERSONAL_RECORDS_QUERY).as_str(), &[&session_id])?;
+        Ok(query.iter().map(Db::row_to_personal_record).collect())
+    }
+
+    const PERSONAL_RECORDS_QUERY: &'static str = "SELECT p.lift_id, e.name, l.date, p.kind, l.load, l.reps, p.value, p.previous FROM personal_records p JOIN lift_loads l ON l.id=p.lift_id JOIN exercises e ON e.id=l.exercise_id";
+
+    fn row_to_personal_record(row: &Row) -> PersonalRecord {
+        let kind: String = row.get(3);
+        PersonalRecord {lift_id: row.get(0), exercise_name: row.get(1), date: row.get(2), kind: PrKind::parse(&kind).unwrap_or(PrKind::Weight), load: row.get(4), reps: row.get(5), value: row.get(6), previous: row.get(7)}
+    }
+
+    // Compares a freshly inserted lift with everything lifted before it and stores the PRs it sets.
+    // Only beaten records count, so the first lift of an exercise isn't a PR.
+    pub(crate) fn transaction_detect_prs(transaction: &mut Transaction, lift_id: i32) -> Result<(), Error> {
+        let row = transaction.query_one("
+            WITH cur AS (SELECT * FROM lift_loads WHERE id=$1),
+            hist AS (SELECT h.* FROM lift_loads h, cur WHERE h.exercise_id=cur.exercise_id AND h.id<>cur.id AND (h.date<cur.date OR (h.session_id=cur.session_id AND h.id<cur.id)))
+            SELECT cur.load, cur.reps, cur.e1rm, (cur.load*cur.reps*cur.sets)::REAL,
+                (SELECT MAX(load) FROM hist WHERE hist.reps=cur.reps),
+                (SELECT MAX(reps) FROM hist WHERE hist.load>=cur.load),
+                (SELECT MAX(e1rm) FROM hist),
+                (SELECT MAX(load*reps*sets)::REAL FROM hist)
+            FROM cur;", &[&lift_id])?;
+        let records = [
+            (PrKind::Weight, row.get::<_, f32>(0), row.get::<_, Option<f32>>(4)),
+            (PrKind::Reps, row.get(1), row.get(5)),
+            (PrKind::E1rm, row.get(2), row.get(6)),
+            (PrKind::Volume, row.get(3), row.get(7)),
+        ];
+        for (kind, value, previous) in records {
+            if let Some(previous) = previous {
+                if value > previous {
+                    transaction.execute("INSERT INTO personal_records (lift_id, kind, value, previous) VALUES ($1, $2, $3, $4);", &[&lift_id, &kind.as_str(), &value, &previous])?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Recomputes the PRs of the given exercises from their whole history in one pass, for lifts that were
+    // imported out of order or moved between exercises. Same rules as transaction_detect_prs.
+    pub(crate) fn transaction_recompute_prs(transaction: &mut Transaction, exercise_ids: &[i32]) -> Result<(), Error> {
+        transaction.execute("DELETE FROM personal_records p USING lifts l WHERE l.id=p.lift_id AND l.exercise_id=ANY($1);", &[&exercise_ids])?;
+        let lifts: Vec<PrLift> = transaction.query("SELECT id, exercise_id, session_id, date, load, reps, e1rm, (load*reps*sets)::REAL FROM lift_loads WHERE exercise_id=ANY($1) ORDER BY exercise_id, date, session_id, id;", &[&exercise_ids])?
+            .iter().map(|row| PrLift {id: row.get(0), exercise_id: row.get(1), session_id: row.get(2), date: row.get(3), load: row.get(4), reps: row.get(5), e1rm: row.get(6), volume: row.get(7)}).collect();
+
+        let (mut lift_ids, mut kinds, mut values, mut previous): (Vec<i32>, Vec<&str>, Vec<f32>, Vec<f32>) = Default::default();
+        let mut history = PrHistory::default();
+        let mut session = PrHistory::default();
+        // Lifts of the current date, added to the history once the date is done. Other sessions
+        // on the same date don't count as history, earlier lifts of the same session do.
+        let mut day: Vec<&PrLift> = Vec::new();
+        for (index, lift) in lifts.iter().enumerate() {
+            let last = index.checked_sub(1).map(|i| &lifts[i]);
+            if last.is_some_and(|last| last.exercise_id != lift.exercise_id || last.date != lift.date) {
+                day.drain(..).for_each(|lift| history.add(lift));
+            }
+            if last.is_some_and(|last| last.exercise_id != lift.exercise_id) {
+                history = PrHistory::default();
+            }
+            if last.is_none_or(|last| last.session_id != lift.session_id || last.exercise_id != lift.exercise_id) {
+                session = PrHistory::default();
+            }
+            let before = history.previous(lift);
+            let in_session = session.previous(lift);
+            let current = [lift.load, lift.reps, lift.e1rm, lift.volume];
+            for (i, kind) in [PrKind::Weight, PrKind::Reps, PrKind::E1rm, PrKind::Volume].into_iter().enumerate() {
+                if let Some(best) = max_option(before[i], in_session[i]) {
+                    if current[i] > best {
+                        lift_ids.push(lift.id);
+                        kinds.push(kind.as_str());
+                        values.push(current[i]);
+                        previous.push(best);
+                    }
+                }
+            }
+            session.add(lift);
+            day.push(lift);
+        }
+        transaction.execute("INSERT INTO personal_records (lift_id, kind, value, previous) SELECT * FROM UNNEST($1::INT[], $2::TEXT[], $3::REAL[], $4::REAL[]);", &[&lift_ids, &kinds, &values, &previous])?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lift(load: f32, reps: f32) -> PrLift {
+        let date = NaiveDate::from_ymd_opt(2024, 3, 6).unwrap();
+        PrLift {id: 0, exercise_id: 1, session_id: 1, date, load, reps, e1rm: load * (1.0 + reps / 30.0), volume: load * reps}
+    }
+
+    fn history(lifts: &[(f32, f32)]) -> PrHistory {
+        let mut history = PrHistory::default();
+        lifts.iter().for_each(|(load, reps)| history.add(&lift(*load, *reps)));
+        history
+    }
+
+    #[test]
+    fn starts_without_records() {
+        assert_eq!(PrHistory::default().previous(&lift(100.0, 5.0)), [None; 4]);
+    }
+
+    #[test]
+    fn keeps_the_heaviest_load_by_reps() {
+        let history = history(&[(100.0, 5.0), (110.0, 5.0), (105.0, 5.0), (120.0, 3.0)]);
+        assert_eq!(history.previous(&lift(50.0, 5.0))[0], Some(110.0));
+        assert_eq!(history.previous(&lift(50.0, 3.0))[0], Some(120.0));
+        assert_eq!(history.previous(&lift(50.0, 4.0))[0], None);
+    }
+
+    #[test]
+    fn counts_reps_at_the_load_or_heavier() {
+        let history = history(&[(100.0, 5.0), (90.0, 8.0), (110.0, 3.0)]);
+        assert_eq!(history.previous(&lift(80.0, 1.0))[1], Some(8.0));
+        assert_eq!(history.previous(&lift(95.0, 1.0))[1], Some(5.0));
+        assert_eq!(history.previous(&lift(110.0, 1.0))[1], Some(3.0));
+        assert_eq!(history.previous(&lift(115.0, 1.0))[1], None);
+    }
+
+    #[test]
+    fn keeps_only_unbeaten_lifts_in_the_frontier() {
+        let mut history = history(&[(100.0, 5.0), (90.0, 4.0), (100.0, 5.0)]);
+        assert_eq!(history.frontier, vec![(100.0, 5.0)]);
+        history.add(&lift(110.0, 3.0));
+        history.add(&lift(100.0, 6.0));
+        assert_eq!(history.frontier, vec![(110.0, 3.0), (100.0, 6.0)]);
+        history.add(&lift(120.0, 8.0));
+        assert_eq!(history.frontier, vec![(120.0, 8.0)]);
+    }
+
+    #[test]
+    fn keeps_the_best_e1rm_and_volume() {
+        let history = history(&[(100.0, 5.0), (120.0, 1.0), (60.0, 20.0)]);
+        let previous = history.previous(&lift(50.0, 5.0));
+        assert_eq!(previous[2], Some(120.0 * (1.0 + 1.0 / 30.0)));
+        assert_eq!(previous[3], Some(1200.0));
+    }
+}
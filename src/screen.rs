@@ -0,0 +1,741 @@
+use std::env;
+use std::error::Error;
+use std::io::{self, IsTerminal, Write};
+use std::time::Instant;
+
+use chrono::{Datelike, NaiveDate};
+use postgres::Transaction;
+use ratatui::DefaultTerminal;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Clear, List, ListItem, ListState, Paragraph};
+use ratatui::Frame;
+
+use liftsql::{Db, HistoryLift, LiftEntry, NewLift, PersonalRecord, Unit};
+
+use crate::draft::Draft;
+use crate::timer;
+use crate::{PendingLift, PlannedLift, Tui};
+
+const HISTORY_LIMIT: i64 = 200;
+
+// Full screen needs a real terminal on both ends. Everything else gets the line-based menu.
+pub fn is_supported() -> bool {
+    let term = env::var("TERM").unwrap_or_default();
+    io::stdin().is_terminal() && io::stdout().is_terminal() && !term.is_empty() && term != "dumb"
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Pane {
+    Entry,
+    Plan,
+    Session,
+    History,
+    Prs,
+}
+
+impl Pane {
+    const ALL: [Pane; 5] = [Pane::Entry, Pane::Plan, Pane::Session, Pane::History, Pane::Prs];
+
+    fn next(self, step: isize) -> Pane {
+        let index = Pane::ALL.iter().position(|p| *p == self).unwrap_or(0) as isize;
+        Pane::ALL[(index + step).rem_euclid(Pane::ALL.len() as isize) as usize]
+    }
+}
+
+// The rest after a lift of an exercise with a rest period. The next key ends it.
+struct Rest {
+    start: Instant,
+    seconds: i32,
+    // Index of the lift in the session.
+    lift: usize,
+    rang: bool,
+}
+
+impl Rest {
+    fn remaining(&self) -> i32 {
+        self.seconds - self.start.elapsed().as_secs() as i32
+    }
+}
+
+// Session details typed into the entry line instead of a lift.
+#[derive(Clone, Copy, PartialEq)]
+enum Prompt {
+    Date,
+    // A date in the future is only taken after a yes.
+    FutureDate(NaiveDate),
+    Bodyweight,
+}
+
+struct Screen {
+    focus: Pane,
+    date: NaiveDate,
+    plan_name: String,
+    plan: Vec<PlannedLift>,
+    plan_state: ListState,
+    session: Vec<PendingLift>,
+    session_state: ListState,
+    // In kg, only known for a resumed draft.
+    bodyweight: Option<f32>,
+    history: Vec<HistoryLift>,
+    history_state: ListState,
+    prs: Vec<PersonalRecord>,
+    prs_state: ListState,
+    exercises: Vec<(i32, String)>,
+    aliases: Vec<(i32, String)>,
+    entry: String,
+    // Index of the session lift the entry line replaces, which stays as it is until the edit is added.
+    editing: Option<usize>,
+    prompt: Option<(Prompt, String)>,
+    rest: Option<Rest>,
+    // The PRs the session would set, while it's reviewed before saving.
+    review: Option<Vec<PersonalRecord>>,
+    // A draft from an earlier run, until it's resumed or discarded.
+    draft: Option<Draft>,
+    // The draft matches a session that's already logged, so resuming it would log it twice.
+    draft_logged: bool,
+    status: String,
+    // Set after the first attempt to quit with unsaved lifts.
+    confirm_quit: bool,
+}
+
+// True when the line-based menu was asked for. Unsaved lifts stay in the draft for it to pick up.
+pub fn run(tui: &mut Tui) -> Result<bool, Box<dyn Error>> {
+    let mut screen = Screen::load(tui)?;
+    let mut terminal = ratatui::init();
+    let result = screen.event_loop(tui, &mut terminal);
+    ratatui::restore();
+    result
+}
+
+impl Screen {
+    fn load(tui: &mut Tui) -> Result<Screen, Box<dyn Error>> {
+        let day_id = tui.read_config().map(|config| config.day_id).unwrap_or(0);
+        let (plan_name, plan) = tui.select_plan_day(day_id)?.unwrap_or_default();
+        let mut screen = Screen {
+            focus: Pane::Entry,
+            date: tui.db.select_current_date()?,
+            plan_name: format!("{} (day {} of {})", plan_name, day_id + 1, tui.plan.len()),
+            plan,
+            plan_state: ListState::default(),
+            session: Vec::new(),
+            session_state: ListState::default(),
+            bodyweight: None,
+            history: Vec::new(),
+            history_state: ListState::default(),
+            prs: Vec::new(),
+            prs_state: ListState::default(),
+            exercises: tui.db.select_exercises()?,
+            aliases: tui.db.select_exercise_aliases()?,
+            entry: String::new(),
+            editing: None,
+            prompt: None,
+            rest: None,
+            review: None,
+            draft: None,
+            draft_logged: false,
+            status: String::from("Type a lift like 'squat 100x5x3' and press Enter."),
+            confirm_quit: false,
+        };
+        match Draft::read() {
+            Ok(Some(draft)) => {
+                screen.draft_logged = draft.logged(&mut tui.db)?;
+                screen.status = match screen.draft_logged {
+                    true => format!("The unsaved session of {} is ALREADY LOGGED with the same {} lifts. Enter or d discards it, y logs it a second time.", draft.date.format("%d.%m.%Y"), draft.lifts.len()),
+                    false => format!("Resume the unsaved session of {} with {} lifts? Enter or y resumes it, d discards it.", draft.date.format("%d.%m.%Y"), draft.lifts.len()),
+                };
+                screen.draft = Some(draft);
+            },
+            Ok(None) => {},
+            Err(err) => screen.status = format!("Couldn't read the session draft: {}", err),
+        }
+        screen.reload(tui)?;
+        screen.plan_state.select(if screen.plan.is_empty() { None } else { Some(0) });
+        Ok(screen)
+    }
+
+    // History and PRs, newest first.
+    fn reload(&mut self, tui: &mut Tui) -> Result<(), Box<dyn Error>> {
+        let first = NaiveDate::from_ymd_opt(1, 1, 1).unwrap_or(self.date);
+        self.history = tui.db.select_history(None, &first, &self.date, HISTORY_LIMIT)?;
+        self.history.reverse();
+        self.prs = tui.db.select_personal_records(None)?;
+        self.prs.reverse();
+        self.history_state.select(if self.history.is_empty() { None } else { Some(0) });
+        self.prs_state.select(if self.prs.is_empty() { None } else { Some(0) });
+        Ok(())
+    }
+
+    fn event_loop(&mut self, tui: &mut Tui, terminal: &mut DefaultTerminal) -> Result<bool, Box<dyn Error>> {
+        loop {
+            terminal.draw(|frame| self.draw(frame, tui.unit))?;
+            // The countdown needs redrawing while nothing is typed.
+            if self.rest.is_some() && !event::poll(timer::TICK)? {
+                self.ring_after_rest();
+                continue;
+            }
+            if let Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                // Esc only ends the rest, other keys go on to what they do.
+                if self.end_rest() && key.code == KeyCode::Esc {
+                    continue;
+                }
+                if key.code == KeyCode::Char('o') && key.modifiers.contains(KeyModifiers::CONTROL) {
+                    return Ok(true);
+                }
+                if !self.handle_key(tui, key)? {
+                    return Ok(false);
+                }
+            }
+        }
+    }
+
+    // The bell goes once the planned rest is over.
+    fn ring_after_rest(&mut self) {
+        if let Some(rest) = self.rest.as_mut().filter(|rest| !rest.rang && rest.remaining() <= 0) {
+            rest.rang = true;
+            let _ = io::stdout().write_all(b"\x07");
+            let _ = io::stdout().flush();
+        }
+    }
+
+    // The rest actually taken goes with the lift before it. False without a rest going on.
+    fn end_rest(&mut self) -> bool {
+        let Some(rest) = self.rest.take() else {
+            return false;
+        };
+        let rested = rest.start.elapsed().as_secs() as i32;
+        if let Some(pending) = self.session.get_mut(rest.lift) {
+            pending.lift.rest = Some(rested);
+            self.status = format!("Rested {}.", timer::format(rested));
+            self.sync_draft();
+        }
+        true
+    }
+
+    // False once the screen should close.
+    fn handle_key(&mut self, tui: &mut Tui, key: KeyEvent) -> Result<bool, Box<dyn Error>> {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        if self.draft.is_some() {
+            return self.handle_draft_key(tui, key);
+        }
+        if self.review.is_some() {
+            return self.handle_review_key(tui, key);
+        }
+        if self.prompt.is_some() {
+            self.handle_prompt_key(tui, key)?;
+            return Ok(true);
+        }
+        let quit_attempt = match key.code {
+            KeyCode::Char('c') if ctrl => true,
+            KeyCode::Esc => self.focus != Pane::Entry || (self.entry.is_empty() && self.editing.is_none()),
+            KeyCode::Char('q') => self.focus != Pane::Entry,
+            _ => false,
+        };
+        if quit_attempt {
+            if self.session.is_empty() {
+                return Ok(false);
+            }
+            if self.confirm_quit {
+                Draft::remove()?;
+                return Ok(false);
+            }
+            self.confirm_quit = true;
+            self.status = format!("{} unsaved lifts. Ctrl-S saves them, quitting again discards them.", self.session.len());
+            return Ok(true);
+        }
+        self.confirm_quit = false;
+
+        match key.code {
+            KeyCode::Char('s') if ctrl => self.review(tui)?,
+            KeyCode::Char('d') if ctrl => self.prompt = Some((Prompt::Date, String::new())),
+            KeyCode::Char('b') if ctrl => self.prompt = Some((Prompt::Bodyweight, String::new())),
+            KeyCode::Tab => self.focus = self.focus.next(1),
+            KeyCode::BackTab => self.focus = self.focus.next(-1),
+            _ => match self.focus {
+                Pane::Entry => self.handle_entry_key(tui, key)?,
+                pane => self.handle_list_key(tui.unit, pane, key),
+            },
+        }
+        Ok(true)
+    }
+
+    // Keeps the unsaved lifts on disk in case the terminal goes away.
+    fn sync_draft(&mut self) {
+        let draft = Draft {date: self.date, bodyweight: self.bodyweight, lifts: self.session.clone()};
+        if let Err(err) = draft.sync() {
+            self.status = format!("Couldn't save the session draft: {}", err);
+        }
+    }
+
+    fn handle_review_key(&mut self, tui: &mut Tui, key: KeyEvent) -> Result<bool, Box<dyn Error>> {
+        match key.code {
+            KeyCode::Enter | KeyCode::Char('y') => self.save(tui)?,
+            KeyCode::Esc | KeyCode::Char('n') | KeyCode::Char('q') => {
+                self.review = None;
+                self.status = String::from("Not saved yet, the lifts can still be changed.");
+            },
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => self.review = None,
+            _ => {},
+        }
+        Ok(true)
+    }
+
+    // Quitting keeps the draft for the next start.
+    fn handle_draft_key(&mut self, tui: &mut Tui, key: KeyEvent) -> Result<bool, Box<dyn Error>> {
+        match key.code {
+            // Enter discards a draft that's already logged.
+            KeyCode::Enter | KeyCode::Char('y') if !self.draft_logged || key.code != KeyCode::Enter => if let Some(draft) = self.draft.take() {
+                self.status = format!("Resumed {} unsaved lifts of {}. Ctrl-S saves them, quitting twice discards them.", draft.lifts.len(), draft.date.format("%d.%m.%Y"));
+                (self.date, self.bodyweight, self.session) = (draft.date, draft.bodyweight, draft.lifts);
+                self.session_state.select(self.session.len().checked_sub(1));
+                self.reload(tui)?;
+            },
+            KeyCode::Enter | KeyCode::Char('d') | KeyCode::Char('n') => {
+                self.draft = None;
+                Draft::remove()?;
+                self.status = String::from("Discarded the unsaved session. Type a lift like 'squat 100x5x3' and press Enter.");
+            },
+            KeyCode::Esc | KeyCode::Char('q') => return Ok(false),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(false),
+            _ => {},
+        }
+        Ok(true)
+    }
+
+    fn handle_prompt_key(&mut self, tui: &mut Tui, key: KeyEvent) -> Result<(), Box<dyn Error>> {
+        let Some((prompt, input)) = &mut self.prompt else {
+            return Ok(());
+        };
+        match key.code {
+            KeyCode::Enter => {
+                let prompt = *prompt;
+                let input = input.trim().to_string();
+                match prompt {
+                    Prompt::Date => self.set_date(tui, &input)?,
+                    Prompt::FutureDate(date) if matches!(input.to_lowercase().as_str(), "y" | "yes") => self.apply_date(tui, date)?,
+                    Prompt::FutureDate(_) => {
+                        self.prompt = None;
+                        self.status = format!("Session date kept at {}.", tui.date_order.format(&self.date));
+                    },
+                    Prompt::Bodyweight => self.set_bodyweight(tui.unit, &input),
+                }
+            },
+            KeyCode::Esc => self.prompt = None,
+            KeyCode::Backspace => {
+                input.pop();
+            },
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => input.clear(),
+            KeyCode::Char(c) => input.push(c),
+            _ => {},
+        }
+        Ok(())
+    }
+
+    // The prompt stays open on invalid input.
+    fn set_date(&mut self, tui: &mut Tui, input: &str) -> Result<(), Box<dyn Error>> {
+        let today = tui.db.select_current_date()?;
+        let Some(date) = liftsql::parse_date(input, &today, tui.date_order) else {
+            self.status = format!("Invalid date. Try yesterday, mon, -3d, {} or {}.", today.format("%Y-%m-%d"), tui.date_order.format(&today));
+            return Ok(());
+        };
+        if date > today {
+            self.prompt = Some((Prompt::FutureDate(date), String::new()));
+            self.status = format!("{} {} is in the future.", date.weekday(), tui.date_order.format(&date));
+            return Ok(());
+        }
+        self.apply_date(tui, date)
+    }
+
+    fn apply_date(&mut self, tui: &mut Tui, date: NaiveDate) -> Result<(), Box<dyn Error>> {
+        self.date = date;
+        self.prompt = None;
+        self.status = format!("Session date set to {} {}.", date.weekday(), tui.date_order.format(&date));
+        self.sync_draft();
+        self.reload(tui)
+    }
+
+    // An empty input logs no bodyweight with the session.
+    fn set_bodyweight(&mut self, unit: Unit, input: &str) {
+        if input.is_empty() {
+            self.bodyweight = None;
+            self.prompt = None;
+            self.status = String::from("No bodyweight will be logged with the session.");
+            self.sync_draft();
+            return;
+        }
+        match Unit::parse_weight(input) {
+            Some((weight, weight_unit)) if weight > 0.0 => {
+                let kg = weight_unit.unwrap_or(unit).to_kg(weight);
+                self.bodyweight = Some(kg);
+                self.prompt = None;
+                self.status = format!("Bodyweight {} will be logged with the session.", unit.format(kg));
+                self.sync_draft();
+            },
+            _ => self.status = format!("Invalid bodyweight. Use e.g. 80 or 176{}.", if unit == Unit::Kg { "lb" } else { "" }),
+        }
+    }
+
+    fn handle_entry_key(&mut self, tui: &mut Tui, key: KeyEvent) -> Result<(), Box<dyn Error>> {
+        match key.code {
+            KeyCode::Enter => match self.validate(tui.unit) {
+                Ok(mut lifts) if self.editing.is_some() => {
+                    let index = self.editing.take().filter(|index| *index < self.session.len());
+                    if let Some(index) = index {
+                        Screen::carry_over(&self.session[index].lift, &mut lifts);
+                        self.status = format!("Changed to {}.", lifts.iter().map(Tui::get_pending_lift_string).collect::<Vec<String>>().join(", "));
+                        let count = lifts.len();
+                        self.session.splice(index..=index, lifts);
+                        self.session_state.select(Some(index + count - 1));
+                        self.entry.clear();
+                        self.sync_draft();
+                    }
+                },
+                Ok(lifts) => {
+                    self.status = format!("Added {}.", lifts.iter().map(Tui::get_pending_lift_string).collect::<Vec<String>>().join(", "));
+                    let exercise_id = lifts.last().map(|pending| pending.lift.exercise_id);
+                    self.session.extend(lifts);
+                    self.session_state.select(Some(self.session.len() - 1));
+                    self.entry.clear();
+                    self.sync_draft();
+                    if let Some(exercise_id) = exercise_id {
+                        if let Some(seconds) = tui.db.select_exercise_defaults(exercise_id)?.rest.filter(|seconds| *seconds > 0) {
+                            self.rest = Some(Rest {start: Instant::now(), seconds, lift: self.session.len() - 1, rang: false});
+                        }
+                    }
+                },
+                Err(err) => self.status = err,
+            },
+            KeyCode::Esc => {
+                if self.editing.take().is_some() {
+                    self.status = String::from("Kept the lift as it was.");
+                }
+                self.entry.clear();
+            },
+            KeyCode::Backspace => {
+                self.entry.pop();
+            },
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => self.entry.clear(),
+            KeyCode::Char(c) => self.entry.push(c),
+            _ => {},
+        }
+        Ok(())
+    }
+
+    // Keeps what the entry line can't express from the lift being edited.
+    fn carry_over(original: &NewLift, lifts: &mut [PendingLift]) {
+        for pending in lifts.iter_mut() {
+            pending.lift.notes = original.notes.clone();
+            if pending.lift.exercise_id == original.exercise_id {
+                (pending.lift.prescribed_weight, pending.lift.prescribed_reps) = (original.prescribed_weight, original.prescribed_reps);
+            }
+        }
+        if let Some(last) = lifts.last_mut() {
+            last.lift.rest = original.rest;
+        }
+    }
+
+    fn handle_list_key(&mut self, unit: Unit, pane: Pane, key: KeyEvent) {
+        let (len, state) = match pane {
+            Pane::Plan => (self.plan.len(), &mut self.plan_state),
+            Pane::Session => (self.session.len(), &mut self.session_state),
+            Pane::History => (self.history.len(), &mut self.history_state),
+            Pane::Prs => (self.prs.len(), &mut self.prs_state),
+            Pane::Entry => return,
+        };
+        let selected = state.selected().unwrap_or(0);
+        let scroll_to = |index: usize| if len == 0 { None } else { Some(index.min(len - 1)) };
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => state.select(scroll_to(selected.saturating_sub(1))),
+            KeyCode::Down | KeyCode::Char('j') => state.select(scroll_to(selected + 1)),
+            KeyCode::PageUp => state.select(scroll_to(selected.saturating_sub(10))),
+            KeyCode::PageDown => state.select(scroll_to(selected + 10)),
+            KeyCode::Home | KeyCode::Char('g') => state.select(scroll_to(0)),
+            KeyCode::End | KeyCode::Char('G') => state.select(scroll_to(len.saturating_sub(1))),
+            // Takes a prescription or a pending lift into the entry line to log or correct it.
+            KeyCode::Enter if pane == Pane::Plan => {
+                if let Some(lift) = self.plan.get(selected) {
+                    self.entry = Screen::planned_entry(lift, unit);
+                    self.focus = Pane::Entry;
+                }
+            },
+            KeyCode::Enter if pane == Pane::Session && selected < len => {
+                let pending = &self.session[selected];
+                let lift = &pending.lift;
+                self.entry = format!("{} {}{}x{}x{}{}", pending.exercise_name, lift.weight, lift.unit.as_str(), lift.reps, lift.sets, lift.rpe.map(|rpe| format!(" @{}", rpe)).unwrap_or_default());
+                self.editing = Some(selected);
+                self.focus = Pane::Entry;
+            },
+            // Moves the selected lift up or down.
+            KeyCode::Char('K') if pane == Pane::Session && selected > 0 && selected < len => {
+                self.session.swap(selected, selected - 1);
+                self.session_state.select(Some(selected - 1));
+                self.editing = self.editing.map(|index| Screen::swapped(index, selected, selected - 1));
+                self.sync_draft();
+            },
+            KeyCode::Char('J') if pane == Pane::Session && selected + 1 < len => {
+                self.session.swap(selected, selected + 1);
+                self.session_state.select(Some(selected + 1));
+                self.editing = self.editing.map(|index| Screen::swapped(index, selected, selected + 1));
+                self.sync_draft();
+            },
+            KeyCode::Delete | KeyCode::Char('d') if pane == Pane::Session && selected < len => {
+                let lift = self.session.remove(selected);
+                self.status = format!("Removed {}.", Tui::get_pending_lift_string(&lift));
+                // A removed lift can't be edited any more, the ones after it move up.
+                self.editing = match self.editing {
+                    Some(index) if index == selected => {
+                        self.entry.clear();
+                        None
+                    },
+                    Some(index) if index > selected => Some(index - 1),
+                    editing => editing,
+                };
+                self.session_state.select(if self.session.is_empty() { None } else { Some(selected.min(self.session.len() - 1)) });
+                self.sync_draft();
+            },
+            _ => {},
+        }
+    }
+
+    fn swapped(index: usize, a: usize, b: usize) -> usize {
+        match index {
+            i if i == a => b,
+            i if i == b => a,
+            i => i,
+        }
+    }
+
+    // The lifts the entry line would add, or what's wrong with it.
+    fn validate(&self, unit: Unit) -> Result<Vec<PendingLift>, String> {
+        if self.entry.trim().is_empty() {
+            return Err(String::from("Type a lift like 'squat 100x5x3', 'bench 80x5,5,4' or 'chin +10x8 @8'."));
+        }
+        let mut entry = match LiftEntry::parse(&self.entry) {
+            Some(entry) => entry,
+            None => return Err(String::from("Invalid entry. Use e.g. 'squat 100x5x3', 'bench 80x5,5,4' or 'chin +10x8 @8'.")),
+        };
+        let matching = Tui::match_name_to_exercise(entry.exercise.clone(), &self.exercises, &self.aliases);
+        let exercise = match matching.len() {
+            0 => return Err(match Tui::suggest_exercises(&entry.exercise, &self.exercises, &self.aliases).first() {
+                Some(suggestion) => format!("No exercise matches '{}'. Did you mean {}?", entry.exercise, suggestion.1),
+                None => format!("No exercise matches '{}'.", entry.exercise),
+            }),
+            1 => matching[0].clone(),
+            _ => return Err(format!("'{}' matches {}.", entry.exercise, Tui::get_matches_string(&matching))),
+        };
+
+        // Whatever the entry leaves out comes from the plan.
+        if let Some(planned) = self.plan.iter().find(|p| p.exercise_id == exercise.0) {
+            if entry.weight.is_none() {
+                entry.weight = planned.weight.map(|w| ((unit.from_kg(w) * 100.0).round() / 100.0, Some(unit)));
+            }
+            if entry.reps.is_empty() {
+                entry.reps = planned.reps.into_iter().collect();
+                entry.sets = entry.sets.or(Some(planned.sets as f32));
+            }
+        }
+        let (weight, weight_unit) = match entry.weight {
+            Some((weight, weight_unit)) => (weight, weight_unit.unwrap_or(unit)),
+            None => return Err(format!("{}: weight missing, e.g. '{} 100x5'.", exercise.1, entry.exercise)),
+        };
+        if entry.reps.is_empty() {
+            return Err(format!("{}: reps missing, e.g. '{} {}x5'.", exercise.1, entry.exercise, weight));
+        }
+        // Kept so missed prescriptions can be spotted later. AMRAP sets have no rep target to miss.
+        let planned = self.plan.iter().find(|p| p.exercise_id == exercise.0);
+        let (prescribed_weight, prescribed_reps) = (planned.and_then(|p| p.weight), planned.and_then(|p| p.reps.filter(|_| !p.amrap)));
+        Ok(entry.rep_groups().into_iter().map(|(reps, sets)| PendingLift {
+            exercise_name: exercise.1.clone(),
+            lift: NewLift {exercise_id: exercise.0, weight, unit: weight_unit, reps, sets, prescribed_weight, prescribed_reps, notes: None, rpe: entry.rpe, rest: None},
+        }).collect())
+    }
+
+    // The session and its lifts, with the PRs they set.
+    fn insert_session(&self, transaction: &mut Transaction) -> Result<Vec<PersonalRecord>, Box<dyn Error>> {
+        let session_id = Db::transaction_insert_session(transaction, &self.date)?;
+        if let Some(bodyweight) = self.bodyweight {
+            Db::transaction_insert_bodyweight(transaction, &self.date, bodyweight)?;
+        }
+        for pending in &self.session {
+            Db::transaction_insert_lift(transaction, session_id, &pending.lift)?;
+        }
+        Ok(Db::transaction_select_session_prs(transaction, session_id)?)
+    }
+
+    // The PRs are found by logging the session and rolling it back again.
+    fn review(&mut self, tui: &mut Tui) -> Result<(), Box<dyn Error>> {
+        if self.session.is_empty() {
+            self.status = String::from("Nothing to save yet.");
+            return Ok(());
+        }
+        let mut transaction = tui.db.transaction_start()?;
+        let prs = self.insert_session(&mut transaction)?;
+        Db::transaction_rollback(transaction)?;
+        self.review = Some(prs);
+        Ok(())
+    }
+
+    fn save(&mut self, tui: &mut Tui) -> Result<(), Box<dyn Error>> {
+        self.review = None;
+        let mut transaction = tui.db.transaction_start()?;
+        let prs = self.insert_session(&mut transaction)?;
+        Db::transaction_commit(transaction)?;
+
+        self.status = match prs.len() {
+            0 => format!("Saved {} lifts on {}.", self.session.len(), self.date.format("%d.%m.%Y")),
+            n => format!("Saved {} lifts on {} with {} new PRs!", self.session.len(), self.date.format("%d.%m.%Y"), n),
+        };
+        self.session.clear();
+        self.editing = None;
+        self.session_state.select(None);
+        self.bodyweight = None;
+        self.sync_draft();
+        self.reload(tui)
+    }
+
+    fn planned_entry(lift: &PlannedLift, unit: Unit) -> String {
+        let mut entry = lift.exercise_name.clone();
+        if let Some(weight) = lift.weight {
+            entry.push_str(&format!(" {}", (unit.from_kg(weight) * 100.0).round() / 100.0));
+            if let Some(reps) = lift.reps {
+                entry.push_str(&format!("x{}x{}", reps, lift.sets));
+            }
+        }
+        entry
+    }
+
+    fn draw(&mut self, frame: &mut Frame, unit: Unit) {
+        let [top, bottom, entry_area, help_area] = Layout::vertical([Constraint::Percentage(50), Constraint::Fill(1), Constraint::Length(3), Constraint::Length(1)]).areas(frame.area());
+        let [plan_area, session_area] = Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(top);
+        let [history_area, prs_area] = Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(bottom);
+
+        let plan: Vec<ListItem> = self.plan.iter().map(|lift| {
+            let weight = lift.weight.map(|w| unit.format(w)).unwrap_or(format!("?{}", unit.as_str()));
+            let reps = lift.reps.map(|r| r.to_string()).unwrap_or(String::from("?"));
+            let mut line = vec![Span::raw(format!("{} {}x{} {}", lift.exercise_name, lift.sets, reps, weight))];
+            if lift.amrap {
+                line.push(Span::raw(" *AMRAP*").yellow());
+            }
+            if lift.pr_attempt {
+                line.push(Span::raw(" *PR*").yellow());
+            }
+            if lift.stall.is_some() {
+                line.push(Span::raw(" stalled").red());
+            }
+            if self.session.iter().any(|pending| pending.lift.exercise_id == lift.exercise_id) {
+                line.push(Span::raw(" ✓").green());
+            }
+            ListItem::new(Line::from(line))
+        }).collect();
+        let plan_title = format!("Plan: {}", self.plan_name);
+        self.draw_list(frame, plan_area, &plan_title, Pane::Plan, plan);
+
+        let session: Vec<ListItem> = self.session.iter().map(|lift| ListItem::new(Tui::get_pending_lift_string(lift))).collect();
+        let bodyweight = self.bodyweight.map(|w| format!(", bodyweight {}", unit.format(w))).unwrap_or_default();
+        let session_title = format!("Session {}{}{}", self.date.format("%d.%m.%Y"), bodyweight, if self.session.is_empty() { "" } else { " (unsaved)" });
+        self.draw_list(frame, session_area, &session_title, Pane::Session, session);
+
+        let history: Vec<ListItem> = self.history.iter().map(|lift| {
+            let rpe = lift.rpe.map(|rpe| format!(" @{}", rpe)).unwrap_or_default();
+            ListItem::new(format!("{} {} {}x{}x{}{}", lift.date.format("%d.%m.%Y"), lift.exercise_name, unit.format(lift.weight), lift.reps, lift.sets, rpe))
+        }).collect();
+        self.draw_list(frame, history_area, "History", Pane::History, history);
+
+        let prs: Vec<ListItem> = self.prs.iter().map(|pr| ListItem::new(format!("{} {}", pr.date.format("%d.%m.%Y"), Tui::get_pr_string(pr, unit)))).collect();
+        self.draw_list(frame, prs_area, "PRs", Pane::Prs, prs);
+
+        // Live validation of the entry line.
+        let (hint, color) = match self.validate(unit) {
+            Ok(lifts) if self.editing.is_some() => (format!("Enter changes the lift to {}", lifts.iter().map(Tui::get_pending_lift_string).collect::<Vec<String>>().join(", ")), Color::Green),
+            Ok(lifts) => (format!("Enter adds {}", lifts.iter().map(Tui::get_pending_lift_string).collect::<Vec<String>>().join(", ")), Color::Green),
+            Err(_) if self.entry.is_empty() => match &self.rest {
+                Some(rest) if rest.remaining() <= 0 => (timer::countdown(rest.remaining()), Color::Yellow),
+                Some(rest) => (timer::countdown(rest.remaining()), Color::Cyan),
+                None => (self.status.clone(), Color::Gray),
+            },
+            Err(err) => (err, Color::Red),
+        };
+        let (title, text, hint, color) = match &self.prompt {
+            _ if self.draft.is_some() && self.draft_logged => ("Unsaved session already logged", &self.entry, self.status.clone(), Color::Red),
+            _ if self.draft.is_some() => ("Unsaved session", &self.entry, self.status.clone(), Color::Yellow),
+            Some((Prompt::Date, input)) => ("Session date, e.g. yesterday, mon or -2d", input, self.status.clone(), Color::Gray),
+            Some((Prompt::FutureDate(_), input)) => ("Log on a date in the future anyway? (yes/[NO])", input, self.status.clone(), Color::Yellow),
+            Some((Prompt::Bodyweight, input)) => ("Bodyweight, empty for none", input, self.status.clone(), Color::Gray),
+            None if self.editing.is_some() => ("Edit lift, Esc keeps it as it was", &self.entry, hint, color),
+            None => ("Lift", &self.entry, hint, color),
+        };
+        let entry_block = Block::bordered().title(title).title_bottom(Line::from(hint).fg(color)).border_style(self.border_style(Pane::Entry));
+        frame.render_widget(Paragraph::new(text.as_str()).block(entry_block), entry_area);
+        if self.focus == Pane::Entry || self.prompt.is_some() {
+            frame.set_cursor_position((entry_area.x + 1 + text.chars().count() as u16, entry_area.y + 1));
+        }
+
+        let help = match (self.prompt.is_some(), self.focus) {
+            _ if self.draft.is_some() && self.draft_logged => "y log it again  Enter/d discard  q quit and decide later",
+            _ if self.draft.is_some() => "y resume  d discard  q quit and decide later",
+            (true, _) => "Enter set  Esc cancel",
+            (false, Pane::Entry) if self.editing.is_some() => "Enter change the lift  Esc keep it as it was  Tab next pane",
+            (false, Pane::Entry) => "Enter add  Tab next pane  Ctrl-S save session  Ctrl-D date  Ctrl-B bodyweight  Ctrl-O menu  Esc clear/quit",
+            (false, Pane::Plan) => "↑↓ scroll  Enter log this  Tab next pane  Ctrl-S save  Ctrl-O menu  q quit",
+            (false, Pane::Session) => "↑↓ scroll  J/K move  Enter edit  d delete  Tab next pane  Ctrl-S save  Ctrl-O menu  q quit",
+            _ => "↑↓ PgUp PgDn scroll  Tab next pane  Ctrl-S save  Ctrl-O menu  q quit",
+        };
+        let status = if self.entry.is_empty() { String::new() } else { format!("  |  {}", self.status) };
+        frame.render_widget(Paragraph::new(format!("{}{}", help, status)).dark_gray(), help_area);
+
+        if let Some(prs) = &self.review {
+            self.draw_review(frame, unit, prs);
+        }
+    }
+
+    fn draw_review(&self, frame: &mut Frame, unit: Unit, prs: &[PersonalRecord]) {
+        let mut lines: Vec<Line> = self.session.iter().map(|lift| Line::from(Tui::get_pending_lift_string(lift))).collect();
+        if let Some(bodyweight) = self.bodyweight {
+            lines.push(Line::from(format!("Bodyweight {}", unit.format(bodyweight))));
+        }
+        lines.push(Line::from(""));
+        match prs.is_empty() {
+            true => lines.push(Line::from("No new PRs.")),
+            false => lines.extend(prs.iter().map(|pr| Line::from(format!("*** {}", Tui::get_pr_string(pr, unit))).yellow())),
+        }
+        let height = (lines.len() as u16 + 2).min(frame.area().height);
+        let [_, area, _] = Layout::vertical([Constraint::Fill(1), Constraint::Length(height), Constraint::Fill(1)]).areas(frame.area());
+        let [_, area, _] = Layout::horizontal([Constraint::Percentage(15), Constraint::Percentage(70), Constraint::Percentage(15)]).areas(area);
+        let title = format!("Log the session of {}?", self.date.format("%d.%m.%Y"));
+        let block = Block::bordered().title(title).title_bottom("Enter or y logs it, Esc or n goes back").border_style(Style::default().fg(Color::Yellow));
+        frame.render_widget(Clear, area);
+        frame.render_widget(Paragraph::new(lines).block(block), area);
+    }
+
+    fn draw_list(&mut self, frame: &mut Frame, area: Rect, title: &str, pane: Pane, items: Vec<ListItem>) {
+        let block = Block::bordered().title(title.to_string()).border_style(self.border_style(pane));
+        let empty = items.is_empty();
+        let list = List::new(items).block(block).highlight_symbol("> ").highlight_style(match self.focus == pane {
+            true => Style::new().reversed(),
+            false => Style::new(),
+        });
+        let state = match pane {
+            Pane::Plan => &mut self.plan_state,
+            Pane::Session => &mut self.session_state,
+            Pane::History => &mut self.history_state,
+            Pane::Prs => &mut self.prs_state,
+            Pane::Entry => return,
+        };
+        if empty {
+            state.select(None);
+        }
+        frame.render_stateful_widget(list, area, state);
+    }
+
+    fn border_style(&self, pane: Pane) -> Style {
+        match self.focus == pane {
+            true => Style::new().cyan(),
+            false => Style::new(),
+        }
+    }
+}
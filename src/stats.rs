@@ -0,0 +1,135 @@
+use postgres::Error;
+use chrono::NaiveDate;
+use serde::Serialize;
+
+use crate::{Db, ExerciseFilter};
+
+#[derive(Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Period {
+    Week,
+    Month,
+    // Training blocks start on the dates stored in the blocks table and last until the next one.
+    Block,
+}
+
+impl Period {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Period::Week => "week",
+            Period::Month => "month",
+            Period::Block => "block",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Period> {
+        [Period::Week, Period::Month, Period::Block].into_iter().find(|p| p.as_str() == s.to_lowercase())
+    }
+
+    // Start date of the period containing the date column. Lifts before the first block fall into 01.01.0001.
+    fn sql(&self, date_column: &str) -> String {
+        match self {
+            Period::Week => format!("date_trunc('week', {})::DATE", date_column),
+            Period::Month => format!("date_trunc('month', {})::DATE", date_column),
+            Period::Block => format!("COALESCE((SELECT MAX(b.start_date) FROM blocks b WHERE b.start_date <= {}), DATE '0001-01-01')", date_column),
+        }
+    }
+}
+
+pub struct StatsQuery {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub period: Period,
+    pub filter: ExerciseFilter,
+}
+
+#[derive(Serialize)]
+pub struct TonnageStats {
+    pub period: NaiveDate,
+    pub tonnage: f32,
+    pub sets: f32,
+    pub sessions: i64,
+}
+
+// One value per period and group, e.g. sets per muscle group or intensity per exercise.
+#[derive(Serialize)]
+pub struct GroupStats {
+    pub period: NaiveDate,
+    pub group: String,
+    pub value: f32,
+}
+
+impl Db {
+    pub fn insert_block(&mut self, name: &str, start_date: &NaiveDate) -> Result<(), Error> {
+        self.client.execute("INSERT INTO blocks (name, start_date) VALUES ($1, $2) ON CONFLICT (start_date) DO UPDATE SET name=EXCLUDED.name;", &[&name, &start_date])?;
+        Ok(())
+    }
+
+    pub fn select_blocks(&mut self) -> Result<Vec<(NaiveDate, String)>, Error> {
+        let query = self.client.query("SELECT start_date, name FROM blocks ORDER BY start_date;", &[])?;
+        let mut ret: Vec<(NaiveDate, String)> = Vec::new();
+        for block in query {
+            ret.push((block.get(0), block.get(1)));
+        }
+        Ok(ret)
+    }
+
+    // Tonnage is load x reps x sets in kg.
+    pub fn select_tonnage(&mut self, stats: &StatsQuery) -> Result<Vec<TonnageStats>, Error> {
+        let (category, equipment, muscle, unilateral) = stats.filter.params();
+        let query = self.client.query(format!("
+            SELECT {} AS period, SUM(l.load*l.reps*l.sets)::REAL, SUM(l.sets)::REAL, COUNT(DISTINCT l.session_id)
+            FROM lift_loads l JOIN exercises e ON e.id=l.exercise_id
+            WHERE l.date BETWEEN $1 AND $2 AND {}
+            GROUP BY period ORDER BY period;", stats.period.sql("l.date"), ExerciseFilter::sql(3)).as_str(), &[&stats.from, &stats.to, &category, &equipment, &muscle, &unilateral])?;
+        let mut ret: Vec<TonnageStats> = Vec::new();
+        for row in query {
+            ret.push(TonnageStats {period: row.get(0), tonnage: row.get(1), sets: row.get(2), sessions: row.get(3)});
+        }
+        Ok(ret)
+    }
+
+    pub fn select_tonnage_by_category(&mut self, stats: &StatsQuery) -> Result<Vec<GroupStats>, Error> {
+        self.select_group_stats(stats, "COALESCE(e.category, 'uncategorized')", "SUM(l.load*l.reps*l.sets)::REAL", "")
+    }
+
+    // Primary muscles count a full set, secondary muscles half a set.
+    pub fn select_sets_per_muscle(&mut self, stats: &StatsQuery) -> Result<Vec<GroupStats>, Error> {
+        self.select_group_stats(stats, "m.muscle", "SUM(l.sets * CASE WHEN m.is_primary THEN 1 ELSE 0.5 END)::REAL", "JOIN exercise_muscles m ON m.exercise_id=l.exercise_id")
+    }
+
+    // Average load in percent of the best e1RM up to that lift, weighted by sets.
+    pub fn select_intensity(&mut self, stats: &StatsQuery) -> Result<Vec<GroupStats>, Error> {
+        let (category, equipment, muscle, unilateral) = stats.filter.params();
+        let query = self.client.query(format!("
+            SELECT {} AS period, e.name AS exercise, (SUM(l.sets * l.load / l.best) / SUM(l.sets) * 100)::REAL AS intensity
+            FROM (SELECT *, MAX(e1rm) OVER (PARTITION BY exercise_id ORDER BY date, id ROWS UNBOUNDED PRECEDING) AS best FROM lift_loads) l
+            JOIN exercises e ON e.id=l.exercise_id
+            WHERE l.date BETWEEN $1 AND $2 AND l.best > 0 AND {}
+            GROUP BY period, exercise ORDER BY period, intensity DESC;", stats.period.sql("l.date"), ExerciseFilter::sql(3)).as_str(), &[&stats.from, &stats.to, &category, &equipment, &muscle, &unilateral])?;
+        let mut ret: Vec<GroupStats> = Vec::new();
+        for row in query {
+            ret.push(GroupStats {period: row.get(0), group: row.get(1), value: row.get(2)});
+        }
+        Ok(ret)
+    }
+
+    // Number of sessions each exercise was trained in.
+    pub fn select_frequency(&mut self, stats: &StatsQuery) -> Result<Vec<GroupStats>, Error> {
+        self.select_group_stats(stats, "e.name", "COUNT(DISTINCT l.session_id)::REAL", "")
+    }
+
+    fn select_group_stats(&mut self, stats: &StatsQuery, group: &str, value: &str, join: &str) -> Result<Vec<GroupStats>, Error> {
+        let (category, equipment, muscle, unilateral) = stats.filter.params();
+        let query = self.client.query(format!("
+            SELECT {} AS period, {} AS grp, {} AS value
+            FROM lift_loads l JOIN exercises e ON e.id=l.exercise_id {}
+            WHERE l.date BETWEEN $1 AND $2 AND {}
+            GROUP BY period, grp ORDER BY period, value DESC, grp;", stats.period.sql("l.date"), group, value, join, ExerciseFilter::sql(3)).as_str(), &[&stats.from, &stats.to, &category, &equipment, &muscle, &unilateral])?;
+        let mut ret: Vec<GroupStats> = Vec::new();
+        for row in query {
+            ret.push(GroupStats {period: row.get(0), group: row.get(1), value: row.get(2)});
+        }
+        Ok(ret)
+    }
+}
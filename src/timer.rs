@@ -0,0 +1,50 @@
+use std::io::{self, IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+use ratatui::crossterm::event::{self, Event, KeyEventKind};
+use ratatui::crossterm::terminal;
+
+pub const TICK: Duration = Duration::from_millis(200);
+
+pub fn format(seconds: i32) -> String {
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+// The countdown, then how long the rest has gone on past it.
+pub fn countdown(remaining: i32) -> String {
+    match remaining {
+        r if r > 0 => format!("Rest {}, any key ends it.", format(r)),
+        r => format!("Rest is over ({} extra), any key ends it.", format(-r)),
+    }
+}
+
+// Counts down the rest after a lift and rings the bell once it's over. Any key ends the rest, which
+// may go on past the planned time. The seconds actually rested, or None without a terminal.
+pub fn rest(seconds: i32) -> Option<i32> {
+    if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        return None;
+    }
+    terminal::enable_raw_mode().ok()?;
+    let start = Instant::now();
+    let mut rang = false;
+    let rested = loop {
+        let remaining = seconds - start.elapsed().as_secs() as i32;
+        // The bell goes out with the first line past the planned rest.
+        let bell = if remaining <= 0 && !rang { "\x07" } else { "" };
+        rang = rang || remaining <= 0;
+        print!("\r+ ... {}\x1b[K{}", countdown(remaining), bell);
+        let _ = io::stdout().flush();
+        match event::poll(TICK) {
+            Ok(true) => match event::read() {
+                Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => break Some(start.elapsed().as_secs() as i32),
+                Ok(_) => {},
+                Err(_) => break None,
+            },
+            Ok(false) => {},
+            Err(_) => break None,
+        }
+    };
+    let _ = terminal::disable_raw_mode();
+    println!();
+    rested
+}